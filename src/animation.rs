@@ -0,0 +1,226 @@
+use std::{io::{self, Write}, time::Duration};
+
+use crossterm::{cursor, event::{self, Event, KeyCode, KeyEvent, KeyEventKind}, execute, queue, terminal};
+
+const MIN_DELAY: Duration = Duration::from_millis(10);
+const MAX_DELAY: Duration = Duration::from_millis(5000);
+
+/// A single picture of a simulation, drawn top to bottom, with a caption shown underneath.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    pub caption: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Control { PlayPause, StepForward, StepBack, Faster, Slower, First, Last, Quit }
+
+impl Control {
+    fn from_key(key: KeyCode) -> Option<Self> {
+        match key {
+            KeyCode::Char(' ') => Some(Control::PlayPause),
+            KeyCode::Right | KeyCode::Char('n') => Some(Control::StepForward),
+            KeyCode::Left | KeyCode::Char('p') => Some(Control::StepBack),
+            KeyCode::Up | KeyCode::Char('+') => Some(Control::Faster),
+            KeyCode::Down | KeyCode::Char('-') => Some(Control::Slower),
+            KeyCode::Home => Some(Control::First),
+            KeyCode::End => Some(Control::Last),
+            KeyCode::Esc | KeyCode::Char('q') => Some(Control::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Which frame is on screen and how fast we are moving through them, independent of the terminal.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Playback {
+    frame: usize,
+    frame_count: usize,
+    playing: bool,
+    delay: Duration,
+    finished: bool,
+}
+
+impl Playback {
+    pub fn new(frame_count: usize, delay: Duration) -> Self {
+        Playback { frame: 0, frame_count, playing: false, delay: delay.clamp(MIN_DELAY, MAX_DELAY), finished: false }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    fn last_frame(&self) -> usize {
+        self.frame_count.saturating_sub(1)
+    }
+
+    pub fn apply(&mut self, control: Control) {
+        match control {
+            Control::PlayPause => self.playing = !self.playing && self.frame < self.last_frame(),
+            Control::StepForward => {
+                self.playing = false;
+                self.frame = usize::min(self.frame + 1, self.last_frame());
+            },
+            Control::StepBack => {
+                self.playing = false;
+                self.frame = self.frame.saturating_sub(1);
+            },
+            Control::Faster => self.delay = (self.delay / 2).max(MIN_DELAY),
+            Control::Slower => self.delay = (self.delay * 2).min(MAX_DELAY),
+            Control::First => self.frame = 0,
+            Control::Last => self.frame = self.last_frame(),
+            Control::Quit => self.finished = true,
+        }
+    }
+
+    /// Advances one frame while playing, pausing on the last frame.
+    pub fn tick(&mut self) {
+        if self.playing {
+            self.frame = usize::min(self.frame + 1, self.last_frame());
+            self.playing = self.frame < self.last_frame();
+        }
+    }
+
+    fn status(&self) -> String {
+        let state: &str = if self.playing { "playing" } else { "paused" };
+        format!("frame {}/{} | {state} | {}ms per frame | space: play/pause, \u{2190}/\u{2192}: step, +/-: speed, q: quit", self.frame + 1, self.frame_count, self.delay.as_millis())
+    }
+}
+
+fn draw(out: &mut impl Write, frame: &Frame, playback: &Playback) -> io::Result<()> {
+    queue!(out, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+    // raw mode does not translate \n into a carriage return, so lines are ended explicitly
+    for line in frame.lines.iter() {
+        write!(out, "{line}\r\n")?;
+    }
+    write!(out, "\r\n{}\r\n{}\r\n", frame.caption, playback.status())?;
+    out.flush()
+}
+
+fn run(out: &mut impl Write, frames: &[Frame], playback: &mut Playback) -> io::Result<()> {
+    while !playback.is_finished() {
+        draw(out, &frames[playback.frame()], playback)?;
+        // while paused there is nothing to do until a key arrives, so block on the read
+        if !playback.is_playing() || event::poll(playback.delay())? {
+            if let Event::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event::read()? {
+                if let Some(control) = Control::from_key(code) {
+                    playback.apply(control);
+                }
+            }
+        } else {
+            playback.tick();
+        }
+    }
+    Ok(())
+}
+
+/// Plays `frames` in the terminal until the user quits, starting paused on the first frame.
+pub fn play(frames: Vec<Frame>, delay: Duration) -> io::Result<()> {
+    if frames.is_empty() {
+        return Ok(());
+    }
+    let mut out = io::stdout();
+    let mut playback: Playback = Playback::new(frames.len(), delay);
+    terminal::enable_raw_mode()?;
+    execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+    let result: io::Result<()> = run(&mut out, &frames, &mut playback);
+    // always hand the terminal back, even if drawing failed
+    execute!(out, cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use crate::animation::*;
+
+    fn playback() -> Playback {
+        Playback::new(3, Duration::from_millis(200))
+    }
+
+    #[test]
+    fn starts_paused_on_first_frame() {
+        let playback = playback();
+        assert_eq!(playback.frame(), 0);
+        assert!(!playback.is_playing());
+    }
+
+    #[test]
+    fn tick_only_advances_while_playing() {
+        let mut playback = playback();
+        playback.tick();
+        assert_eq!(playback.frame(), 0);
+        playback.apply(Control::PlayPause);
+        playback.tick();
+        assert_eq!(playback.frame(), 1);
+    }
+
+    #[test]
+    fn pauses_on_last_frame() {
+        let mut playback = playback();
+        playback.apply(Control::PlayPause);
+        playback.tick();
+        playback.tick();
+        playback.tick();
+        assert_eq!(playback.frame(), 2);
+        assert!(!playback.is_playing());
+    }
+
+    #[test]
+    fn stepping_pauses_and_stays_in_bounds() {
+        let mut playback = playback();
+        playback.apply(Control::StepBack);
+        assert_eq!(playback.frame(), 0);
+        playback.apply(Control::PlayPause);
+        playback.apply(Control::StepForward);
+        assert!(!playback.is_playing());
+        playback.apply(Control::StepForward);
+        playback.apply(Control::StepForward);
+        assert_eq!(playback.frame(), 2);
+    }
+
+    #[test]
+    fn jump_to_first_and_last() {
+        let mut playback = playback();
+        playback.apply(Control::Last);
+        assert_eq!(playback.frame(), 2);
+        playback.apply(Control::First);
+        assert_eq!(playback.frame(), 0);
+    }
+
+    #[test]
+    fn speed_is_clamped() {
+        let mut playback = Playback::new(3, Duration::from_millis(20));
+        playback.apply(Control::Faster);
+        playback.apply(Control::Faster);
+        assert_eq!(playback.delay(), MIN_DELAY);
+        playback.apply(Control::Slower);
+        assert_eq!(playback.delay(), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn quit_finishes_playback() {
+        let mut playback = playback();
+        playback.apply(Control::Quit);
+        assert!(playback.is_finished());
+    }
+
+    #[test]
+    fn keys_map_to_controls() {
+        assert_eq!(Control::from_key(KeyCode::Char(' ')), Some(Control::PlayPause));
+        assert_eq!(Control::from_key(KeyCode::Right), Some(Control::StepForward));
+        assert_eq!(Control::from_key(KeyCode::Char('x')), None);
+    }
+}
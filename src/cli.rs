@@ -0,0 +1,115 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::PuzzleError;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Command {
+    Solve,
+    Animate,
+    Generate,
+    Check,
+    Render,
+}
+
+impl FromStr for Command {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "solve" => Ok(Command::Solve),
+            "animate" => Ok(Command::Animate),
+            "generate" => Ok(Command::Generate),
+            "check" => Ok(Command::Check),
+            "render" => Ok(Command::Render),
+            _ => Err(PuzzleError::parse(format!("Unknown command {s}, expected one of: solve, animate, generate, check, render"))),
+        }
+    }
+}
+
+/// Command line arguments of the form `<command> --key value --flag ...`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Args {
+    pub command: Command,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, PuzzleError> {
+        let mut args = args.into_iter().peekable();
+        let command: Command = args.next().ok_or(PuzzleError::parse("No command given"))?.parse()?;
+        let mut options: HashMap<String, String> = HashMap::new();
+        while let Some(arg) = args.next() {
+            let key: &str = arg.strip_prefix("--").ok_or(PuzzleError::parse(format!("Expected an option starting with --, found {arg}")))?;
+            // an option without a value is a flag
+            let value: String = args.next_if(|value| !value.starts_with("--")).unwrap_or_default();
+            options.insert(key.to_string(), value);
+        }
+        Ok(Args { command, options })
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>, PuzzleError> {
+        match self.options.get(key) {
+            Some(value) => value.parse::<T>().map(Some).map_err(|_| PuzzleError::parse(format!("Could not parse the value of --{key}: {value}"))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn has(&self, flag: &str) -> bool {
+        self.options.contains_key(flag)
+    }
+
+    pub fn require<T: FromStr>(&self, key: &str) -> Result<T, PuzzleError> {
+        self.get(key)?.ok_or(PuzzleError::parse(format!("Missing required option --{key}")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_command_and_options() {
+        let parsed = Args::parse(args("animate --year 2022 --day 5")).unwrap();
+        assert_eq!(parsed.command, Command::Animate);
+        assert_eq!(parsed.require::<u32>("year"), Ok(2022));
+        assert_eq!(parsed.get::<u8>("day"), Ok(Some(5)));
+        assert_eq!(parsed.get::<u8>("delay"), Ok(None));
+    }
+
+    #[test]
+    fn parse_generate_command() {
+        let parsed = Args::parse(args("generate --year 2023 --day 10 --seed 7 --size 40")).unwrap();
+        assert_eq!(parsed.command, Command::Generate);
+        assert_eq!(parsed.get::<u64>("seed"), Ok(Some(7)));
+        assert_eq!(parsed.get::<usize>("size"), Ok(Some(40)));
+    }
+
+    #[test]
+    fn parse_flags() {
+        let parsed = Args::parse(args("solve --verbose --year 2022")).unwrap();
+        assert_eq!(parsed.get::<String>("verbose"), Ok(Some(String::new())));
+        assert!(parsed.has("verbose") && !parsed.has("quiet"));
+        assert_eq!(parsed.require::<u32>("year"), Ok(2022));
+    }
+
+    #[test]
+    fn parse_unknown_command() {
+        assert!(Args::parse(args("dance --year 2022")).is_err());
+    }
+
+    #[test]
+    fn parse_positional_after_command() {
+        assert!(Args::parse(args("solve 2022")).is_err());
+    }
+
+    #[test]
+    fn missing_and_malformed_options() {
+        let parsed = Args::parse(args("solve --day five")).unwrap();
+        assert!(parsed.require::<u32>("year").is_err());
+        assert!(parsed.get::<u8>("day").is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use std::{fmt::{Debug, Display}, ops::Range};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{Generator, PuzzleError};
+
+/// One way of answering a puzzle, e.g. the brute force or the optimised solution.
+pub struct Variant<T> {
+    pub name: &'static str,
+    pub answer: fn(&[String]) -> Result<T, PuzzleError>,
+}
+
+/// Two variants that gave different answers for the same input, or where only one of them rejected it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Divergence<T> {
+    pub seed: u64,
+    pub input: Vec<String>,
+    pub answers: [(&'static str, Result<T, PuzzleError>); 2],
+}
+
+impl<T: Debug> Display for Divergence<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [(first_name, first), (second_name, second)] = &self.answers;
+        let describe = |answer: &Result<T, PuzzleError>| match answer {
+            Ok(answer) => format!("{answer:?}"),
+            Err(e) => format!("rejected the input: {}", e.details()),
+        };
+        writeln!(f, "{first_name} and {second_name} disagree on the input generated from seed {}", self.seed)?;
+        writeln!(f, "  {first_name}: {}", describe(first))?;
+        writeln!(f, "  {second_name}: {}", describe(second))?;
+        write!(f, "minimised input ({} lines):", self.input.len())?;
+        for line in self.input.iter() {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The two results when the variants give different answers for `input`, or when only one of them
+/// rejects it. Inputs that both variants reject are not divergences, so the minimiser never wanders
+/// off into inputs neither variant accepts.
+fn diverging_answers<T: PartialEq>(input: &[String], variants: &[Variant<T>; 2]) -> Option<[Result<T, PuzzleError>; 2]> {
+    match ((variants[0].answer)(input), (variants[1].answer)(input)) {
+        (Ok(first), Ok(second)) if first == second => None,
+        (Err(_), Err(_)) => None,
+        (first, second) => Some([first, second]),
+    }
+}
+
+/// Greedily removes chunks of lines, halving the chunk size down to single lines, for as long as
+/// the variants keep disagreeing.
+fn minimise<T: PartialEq>(mut input: Vec<String>, variants: &[Variant<T>; 2]) -> Vec<String> {
+    let mut chunk_size: usize = input.len() / 2;
+    while chunk_size > 0 {
+        let mut start: usize = 0;
+        while start < input.len() {
+            let end: usize = usize::min(start + chunk_size, input.len());
+            let candidate: Vec<String> = [&input[..start], &input[end..]].concat();
+            if diverging_answers(&candidate, variants).is_some() {
+                input = candidate;
+            } else {
+                start = end;
+            }
+        }
+        chunk_size /= 2;
+    }
+    input
+}
+
+/// Runs both variants on an input generated from every seed in `seeds`, returning how many inputs
+/// were compared, or the first divergence found with its input minimised.
+pub fn compare<T: PartialEq>(generator: Generator, size: usize, seeds: Range<u64>, variants: [Variant<T>; 2]) -> Result<usize, Box<Divergence<T>>> {
+    let mut compared: usize = 0;
+    for seed in seeds {
+        let input: Vec<String> = generator(&mut StdRng::seed_from_u64(seed), size);
+        if diverging_answers(&input, &variants).is_some() {
+            let input: Vec<String> = minimise(input, &variants);
+            let [first, second] = diverging_answers(&input, &variants).expect("minimised input still diverges");
+            return Err(Box::new(Divergence { seed, input, answers: [(variants[0].name, first), (variants[1].name, second)] }));
+        }
+        compared += 1;
+    }
+    Ok(compared)
+}
+
+#[cfg(test)]
+mod test {
+    use rand::RngExt;
+
+    use crate::differential::*;
+
+    fn numbers(rng: &mut StdRng, size: usize) -> Vec<String> {
+        (0..size).map(|_| rng.random_range(0..10).to_string()).collect()
+    }
+
+    fn sum(lines: &[String]) -> Result<u32, PuzzleError> {
+        lines.iter().map(|line| Ok(line.parse::<u32>()?)).sum()
+    }
+
+    const AGREEING: [Variant<u32>; 2] = [
+        Variant { name: "sum", answer: sum },
+        Variant { name: "fold", answer: |lines| lines.iter().try_fold(0, |acc, line| Ok(acc + line.parse::<u32>()?)) },
+    ];
+
+    // forgets every 7, so it diverges from `sum` exactly when a 7 is present
+    const DIVERGING: [Variant<u32>; 2] = [
+        Variant { name: "sum", answer: sum },
+        Variant { name: "sum without sevens", answer: |lines| Ok(sum(lines)? - 7 * lines.iter().filter(|line| *line == "7").count() as u32) },
+    ];
+
+    #[test]
+    fn agreeing_variants_compare_every_seed() {
+        assert_eq!(compare(numbers, 20, 0..10, AGREEING), Ok(10));
+    }
+
+    #[test]
+    fn divergence_is_minimised_to_one_line() {
+        let divergence: Box<Divergence<u32>> = compare(numbers, 50, 0..10, DIVERGING).unwrap_err();
+        assert_eq!(divergence.input, vec!["7".to_string()]);
+        assert_eq!(divergence.answers, [("sum", Ok(7)), ("sum without sevens", Ok(0))]);
+    }
+
+    #[test]
+    fn input_rejected_by_one_variant_is_a_divergence() {
+        let variants: [Variant<u32>; 2] = [
+            Variant { name: "sum", answer: sum },
+            Variant { name: "rejects everything", answer: |_| Err(PuzzleError::validation("nope")) },
+        ];
+        let divergence: Box<Divergence<u32>> = compare(numbers, 5, 0..3, variants).unwrap_err();
+        assert_eq!(divergence.seed, 0);
+        assert_eq!(divergence.answers, [("sum", Ok(0)), ("rejects everything", Err(PuzzleError::validation("nope")))]);
+    }
+
+    #[test]
+    fn inputs_rejected_by_both_variants_are_skipped() {
+        let rejects = |_: &[String]| Err(PuzzleError::validation("nope"));
+        let variants: [Variant<u32>; 2] = [
+            Variant { name: "rejects everything", answer: rejects },
+            Variant { name: "also rejects everything", answer: rejects },
+        ];
+        assert_eq!(compare(numbers, 5, 0..3, variants), Ok(3));
+    }
+
+    #[test]
+    fn divergence_display_lists_answers_and_input() {
+        let divergence: Divergence<u32> = Divergence { seed: 4, input: vec!["7".to_string()], answers: [("sum", Ok(7)), ("sum without sevens", Ok(0))] };
+        assert_eq!(divergence.to_string(), [
+            "sum and sum without sevens disagree on the input generated from seed 4",
+            "  sum: 7",
+            "  sum without sevens: 0",
+            "minimised input (1 lines):",
+            "7",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn divergence_display_shows_the_rejection() {
+        let divergence: Divergence<u32> = Divergence { seed: 1, input: vec![], answers: [("sum", Ok(0)), ("strict", Err(PuzzleError::validation("nope")))] };
+        assert!(divergence.to_string().contains("  strict: rejected the input: nope"));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{error::Error, fmt::Display, num::ParseIntError};
+
+use regex::Regex;
+
+/// Where in a puzzle input an error was raised. Every field is optional so that
+/// inner parsers can fill in what they know (e.g. the column) and callers can add
+/// the rest (e.g. the line number) on the way out.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Location {
+    pub puzzle: Option<(u32, u8)>,
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PuzzleError {
+    Parse { details: String, location: Location },
+    Validation { details: String, location: Location },
+    Execution { details: String, location: Location },
+}
+
+impl PuzzleError {
+    pub fn parse(details: impl Into<String>) -> Self {
+        PuzzleError::Parse { details: details.into(), location: Location::default() }
+    }
+
+    pub fn validation(details: impl Into<String>) -> Self {
+        PuzzleError::Validation { details: details.into(), location: Location::default() }
+    }
+
+    pub fn execution(details: impl Into<String>) -> Self {
+        PuzzleError::Execution { details: details.into(), location: Location::default() }
+    }
+
+    pub fn details(&self) -> &str {
+        match self {
+            PuzzleError::Parse { details, .. } | PuzzleError::Validation { details, .. } | PuzzleError::Execution { details, .. } => details,
+        }
+    }
+
+    pub fn location(&self) -> &Location {
+        match self {
+            PuzzleError::Parse { location, .. } | PuzzleError::Validation { location, .. } | PuzzleError::Execution { location, .. } => location,
+        }
+    }
+
+    fn location_mut(&mut self) -> &mut Location {
+        match self {
+            PuzzleError::Parse { location, .. } | PuzzleError::Validation { location, .. } | PuzzleError::Execution { location, .. } => location,
+        }
+    }
+
+    fn kind(&self) -> &str {
+        match self {
+            PuzzleError::Parse { .. } => "Parse Error",
+            PuzzleError::Validation { .. } => "Validation Error",
+            PuzzleError::Execution { .. } => "Execution Error",
+        }
+    }
+
+    // The setters below only fill in missing fields, so the innermost (most precise) location wins.
+
+    pub fn in_puzzle(mut self, year: u32, day: u8) -> Self {
+        self.location_mut().puzzle.get_or_insert((year, day));
+        self
+    }
+
+    /// `line` is 1-based, as it would be shown in an editor.
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.location_mut().line.get_or_insert(line);
+        self
+    }
+
+    /// `column` is 1-based, as it would be shown in an editor.
+    pub fn at_column(mut self, column: usize) -> Self {
+        self.location_mut().column.get_or_insert(column);
+        self
+    }
+
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.location_mut().text.get_or_insert(text.into());
+        self
+    }
+
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.location_mut().path.get_or_insert(path.into());
+        self
+    }
+
+    /// Shifts the line number by `lines`, for errors raised while parsing a section that does not start on the first line.
+    pub fn offset_lines(mut self, lines: usize) -> Self {
+        if let Some(line) = self.location_mut().line.as_mut() {
+            *line += lines;
+        }
+        self
+    }
+
+    /// Renders the error like a compiler diagnostic: the message, the file position and,
+    /// when the offending text is known, the line itself with a caret under the failing column.
+    pub fn diagnostic(&self) -> String {
+        let location: &Location = self.location();
+        let mut header: String = self.kind().to_string();
+        if let Some((year, day)) = location.puzzle {
+            header.push_str(&format!(" in {year} day {day}"));
+        }
+        let mut lines: Vec<String> = vec![format!("{header}: {}", self.details())];
+
+        let position: String = match (&location.path, location.line, location.column) {
+            (Some(path), Some(line), Some(column)) => format!("{path}:{line}:{column}"),
+            (Some(path), Some(line), None) => format!("{path}:{line}"),
+            (Some(path), None, _) => path.to_string(),
+            (None, Some(line), Some(column)) => format!("line {line}, column {column}"),
+            (None, Some(line), None) => format!("line {line}"),
+            (None, None, _) => String::new(),
+        };
+        let gutter: String = " ".repeat(location.line.map_or(0, |line| line.to_string().len()));
+        if !position.is_empty() {
+            lines.push(format!("{gutter} --> {position}"));
+        }
+        if let Some(text) = &location.text {
+            lines.push(format!("{gutter} |"));
+            lines.push(format!("{} | {text}", location.line.map_or(String::new(), |line| line.to_string())));
+            if let Some(column) = location.column {
+                // keep tabs so the caret lines up with the text as the terminal renders it
+                let indent: String = text.chars().take(column.saturating_sub(1)).map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+                lines.push(format!("{gutter} | {indent}^"));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let location: &Location = self.location();
+        write!(f, "{}", self.kind())?;
+        if let Some((year, day)) = location.puzzle {
+            write!(f, " in {year} day {day}")?;
+        }
+        match (location.line, location.column) {
+            (Some(line), Some(column)) => write!(f, " on line {line}, column {column}")?,
+            (Some(line), None) => write!(f, " on line {line}")?,
+            (None, Some(column)) => write!(f, " at column {column}")?,
+            (None, None) => {},
+        }
+        write!(f, ": {}", self.details())?;
+        if let Some(text) = &location.text {
+            write!(f, " ({text:?})")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for PuzzleError {}
+
+impl From<ParseIntError> for PuzzleError {
+    fn from(value: ParseIntError) -> Self {
+        PuzzleError::parse(value.to_string())
+    }
+}
+
+impl From<regex::Error> for PuzzleError {
+    fn from(value: regex::Error) -> Self {
+        PuzzleError::parse(value.to_string())
+    }
+}
+
+/// Finds the 1-based column at which `s` stops matching the sequence of `pieces`, each of which
+/// should be anchored with `^`. Used to point at the failing column when a whole-line regex does not match.
+pub fn mismatch_column(s: &str, pieces: &[Regex]) -> usize {
+    let mut offset: usize = 0;
+    for piece in pieces {
+        match piece.find(&s[offset..]) {
+            Some(mat) => offset += mat.end(),
+            None => break,
+        }
+    }
+    s[..offset].chars().count() + 1
+}
+
+/// Parses every line with `parse`, tagging any failure with its line number and text.
+pub fn parse_lines<T>(lines: &[String], parse: impl Fn(&str) -> Result<T, PuzzleError>) -> Result<Vec<T>, PuzzleError> {
+    lines.iter().enumerate().map(|(i, line)| parse(line).map_err(|e| e.at_line(i + 1).with_text(line.as_str()))).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::error::*;
+
+    #[test]
+    fn innermost_location_is_kept() {
+        let err = PuzzleError::parse("bad card").at_column(3).at_line(2).at_column(7).at_line(9);
+        assert_eq!(err.location().line, Some(2));
+        assert_eq!(err.location().column, Some(3));
+    }
+
+    #[test]
+    fn display_includes_location() {
+        let err = PuzzleError::execution("stack 4 does not exist").at_line(12).in_puzzle(2022, 5).with_text("move 1 from 4 to 1");
+        assert_eq!(err.to_string(), "Execution Error in 2022 day 5 on line 12: stack 4 does not exist (\"move 1 from 4 to 1\")");
+    }
+
+    #[test]
+    fn display_without_location() {
+        assert_eq!(PuzzleError::validation("No cycles found in graph").to_string(), "Validation Error: No cycles found in graph");
+    }
+
+    #[test]
+    fn diagnostic_points_at_column() {
+        let err = PuzzleError::parse("Could not parse X into a card").at_column(3).at_line(12).with_text("32X4K 765").with_path("inputs/2023/7/real.txt").in_puzzle(2023, 7);
+        assert_eq!(err.diagnostic(), [
+            "Parse Error in 2023 day 7: Could not parse X into a card",
+            "   --> inputs/2023/7/real.txt:12:3",
+            "   |",
+            "12 | 32X4K 765",
+            "   |   ^",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn diagnostic_without_column() {
+        let err = PuzzleError::parse("Hand and bid are not delimited by whitespace").at_line(2).with_text("32T3K");
+        assert_eq!(err.diagnostic(), [
+            "Parse Error: Hand and bid are not delimited by whitespace",
+            "  --> line 2",
+            "  |",
+            "2 | 32T3K",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn diagnostic_without_location() {
+        assert_eq!(PuzzleError::validation("No cycles found in graph").diagnostic(), "Validation Error: No cycles found in graph");
+    }
+
+    #[test]
+    fn offset_lines_shifts_line_number() {
+        assert_eq!(PuzzleError::parse("bad").at_line(2).offset_lines(3).location().line, Some(5));
+        assert_eq!(PuzzleError::parse("bad").offset_lines(3).location().line, None);
+    }
+
+    #[test]
+    fn mismatch_column_of_partial_match() {
+        let pieces: Vec<Regex> = vec![Regex::new(r"^\w{3}").unwrap(), Regex::new(r"^ = \(").unwrap(), Regex::new(r"^\w{3}").unwrap(), Regex::new(r"^, ").unwrap()];
+        assert_eq!(mismatch_column("bbb = (ccc)", &pieces), 11);
+        assert_eq!(mismatch_column("b = (ccc, zzz)", &pieces), 1);
+    }
+
+    #[test]
+    fn parse_int_error_converts() {
+        let err: PuzzleError = "x".parse::<u32>().unwrap_err().into();
+        assert!(matches!(err, PuzzleError::Parse { .. }));
+    }
+
+    #[test]
+    fn parse_lines_tags_failing_line() {
+        let lines: Vec<String> = vec!["1".to_string(), "2".to_string(), "three".to_string()];
+        let err = parse_lines(&lines, |line| Ok(line.parse::<u32>()?)).unwrap_err();
+        assert_eq!(err.location().line, Some(3));
+        assert_eq!(err.location().text, Some("three".to_string()));
+    }
+}
@@ -1,39 +1,26 @@
-use std::{fs, fmt::Display, error::Error, num::ParseIntError};
+use std::{env, fs, path::{Path, PathBuf}, time::Duration};
 
-mod input;
-use input::*;
+use rand::{rngs::StdRng, SeedableRng};
 
-mod year_2022;
-mod year_2023;
+mod error;
+use error::PuzzleError;
 
-#[derive(Debug, PartialEq, Eq)]
-struct ParseInputError {
-    details: String,
-}
+mod animation;
+use animation::Frame;
 
-impl Display for ParseInputError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Parse Error: {}", {self.details.clone()})
-    }
-}
+mod cli;
+use cli::{Args, Command};
 
-impl Error for ParseInputError {
-    fn description(&self) -> &str {
-        self.details.as_str()
-    }
-}
+mod differential;
 
-impl From<ParseIntError> for ParseInputError {
-    fn from(value: ParseIntError) -> Self {
-        ParseInputError { details: value.to_string() }
-    }
-}
+mod render;
+use render::Canvas;
 
-impl From<regex::Error> for ParseInputError {
-    fn from(value: regex::Error) -> Self {
-        ParseInputError { details: value.to_string() }
-    }
-}
+mod input;
+use input::*;
+
+mod year_2022;
+mod year_2023;
 
 enum AnswerMode {
     Real,
@@ -50,12 +37,14 @@ fn read_problem_input_file(filepath: String) -> Vec<String> {
     contents.split("\n").map(|line| line.to_owned()).collect()
 }
 
-fn get_module(year: u32, day: u8) -> Option<impl Fn(Vec<String>)> {
+type Solver = fn(Vec<String>) -> Result<(), PuzzleError>;
+
+fn get_module(year: u32, day: u8) -> Option<Solver> {
     match year {
         2022 => {
             use crate::year_2022::*;
             match day {
-                1 => Some(day_1::calorie_count::solve as fn(Vec<String>)),
+                1 => Some(day_1::calorie_count::solve as Solver),
                 2 => Some(day_2::rps::solve),
                 3 => Some(day_3::rucksack::solve),
                 4 => Some(day_4::camp_cleanup::solve),
@@ -85,18 +74,197 @@ fn get_module(year: u32, day: u8) -> Option<impl Fn(Vec<String>)> {
     }
 }
 
-fn main() {
-    let year: u32 = get_year(stdin_reader());
-    let day: u8 = get_day(stdin_reader());
+type ConfiguredSolver = fn(Vec<String>, &Args) -> Result<(), PuzzleError>;
+
+/// Solvers that read extra options from the command line, used instead of the plain solver when options are given.
+fn get_configured_module(year: u32, day: u8) -> Option<ConfiguredSolver> {
+    match (year, day) {
+        (2022, 2) => Some(crate::year_2022::day_2::rps::solve_with_options as ConfiguredSolver),
+        (2022, 3) => Some(crate::year_2022::day_3::rucksack::solve_with_options),
+        (2022, 4) => Some(crate::year_2022::day_4::camp_cleanup::solve_with_options),
+        (2022, 5) => Some(crate::year_2022::day_5::supply_stacks::solve_with_options),
+        (2022, 6) => Some(crate::year_2022::day_6::tuning_trouble::solve_with_options),
+        (2023, 1) => Some(crate::year_2023::day_1::artistic_calibration::solve_with_options),
+        (2023, 2) => Some(crate::year_2023::day_2::cube_conundrum::solve_with_options),
+        _ => None,
+    }
+}
+
+type Animator = fn(Vec<String>) -> Result<Vec<Frame>, PuzzleError>;
+
+fn get_animation(year: u32, day: u8) -> Option<Animator> {
+    match (year, day) {
+        (2022, 5) => Some(crate::year_2022::day_5::supply_stacks::animate as Animator),
+        _ => None,
+    }
+}
+
+type Generator = fn(&mut StdRng, usize) -> Vec<String>;
+
+fn get_generator(year: u32, day: u8) -> Option<Generator> {
+    match year {
+        2022 => {
+            use crate::year_2022::*;
+            match day {
+                1 => Some(day_1::calorie_count::generate as Generator),
+                2 => Some(day_2::rps::generate),
+                3 => Some(day_3::rucksack::generate),
+                4 => Some(day_4::camp_cleanup::generate),
+                5 => Some(day_5::supply_stacks::generate),
+                6 => Some(day_6::tuning_trouble::generate),
+                _ => None,
+            }
+        },
+        2023 => {
+            use crate::year_2023::*;
+            match day {
+                1 => Some(day_1::artistic_calibration::generate),
+                2 => Some(day_2::cube_conundrum::generate),
+                3 => Some(day_3::gear_ratio::generate),
+                4 => Some(day_4::scratchcards::generate),
+                5 => Some(day_5::planting_seeds::generate),
+                6 => Some(day_6::boat_race::generate),
+                7 => Some(day_7::camel_cards::generate),
+                8 => Some(day_8::wasteland_traversal::generate),
+                9 => Some(day_9::mirage_maintenance::generate),
+                10 => Some(day_10::pipe_maze::generate),
+                11 => Some(day_11::cosmic_expansion::generate),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+type Renderer = fn(Vec<String>) -> Result<Vec<(String, Canvas)>, PuzzleError>;
+
+fn get_renderer(year: u32, day: u8) -> Option<Renderer> {
+    match (year, day) {
+        (2023, 3) => Some(crate::year_2023::day_3::gear_ratio::render as Renderer),
+        (2023, 10) => Some(crate::year_2023::day_10::pipe_maze::render),
+        (2023, 11) => Some(crate::year_2023::day_11::cosmic_expansion::render),
+        _ => None,
+    }
+}
+
+/// Where a named picture goes: `out.svg` becomes `out-before.svg` for the picture named "before".
+fn picture_path(output: &Path, name: &str) -> PathBuf {
+    match (name, output.file_stem(), output.extension()) {
+        ("", _, _) | (_, None, _) => output.to_path_buf(),
+        (_, Some(stem), Some(extension)) => output.with_file_name(format!("{}-{name}.{}", stem.to_string_lossy(), extension.to_string_lossy())),
+        (_, Some(stem), None) => output.with_file_name(format!("{}-{name}", stem.to_string_lossy())),
+    }
+}
+
+fn solve(year: u32, day: u8, path: String, args: Option<&Args>) {
     if let Some(solver) = get_module(year, day) { 
-        let input_file_path = | solution_type: AnswerMode | get_file_path(solution_type, year, day); 
         println!("Reading answers for day {day} in {year}");
-        solver(read_problem_input_file(input_file_path(AnswerMode::Real)));
+        let lines: Vec<String> = read_problem_input_file(path.clone());
+        let solved: Result<(), PuzzleError> = match (get_configured_module(year, day), args) {
+            (Some(configured), Some(args)) => configured(lines, args),
+            _ => solver(lines),
+        };
+        if let Err(e) = solved {
+            eprintln!("{}", e.in_puzzle(year, day).with_path(path).diagnostic());
+        }
     } else {
         println!("Solution for day {day} in {year} doesn't exist");
     }
 }
 
+fn animate(year: u32, day: u8, path: String, delay: Duration) {
+    if let Some(animator) = get_animation(year, day) {
+        match animator(read_problem_input_file(path.clone())) {
+            Ok(frames) => if let Err(e) = animation::play(frames, delay) {
+                eprintln!("Could not draw the animation: {e}");
+            },
+            Err(e) => eprintln!("{}", e.in_puzzle(year, day).with_path(path).diagnostic()),
+        }
+    } else {
+        println!("Animation for day {day} in {year} doesn't exist");
+    }
+}
+
+/// Writes a generated input to `output`, or to stdout when no file is given. No trailing newline is
+/// written, so the file reads back exactly as generated.
+fn generate(year: u32, day: u8, seed: u64, size: usize, output: Option<String>) {
+    if let Some(generator) = get_generator(year, day) {
+        let input: String = generator(&mut StdRng::seed_from_u64(seed), size).join("\n");
+        match output {
+            Some(path) => if let Err(e) = fs::write(&path, input) {
+                eprintln!("Could not write the generated input to {path}: {e}");
+            },
+            None => print!("{input}"),
+        }
+    } else {
+        println!("Generator for day {day} in {year} doesn't exist");
+    }
+}
+
+/// Compares the variants of a day on `seeds` generated inputs, reporting the first disagreement.
+fn check(year: u32, day: u8, seeds: u64, size: usize) {
+    let outcome: Option<Result<usize, String>> = match (year, day) {
+        (2022, 5) => {
+            use crate::year_2022::day_5::supply_stacks;
+            Some(differential::compare(supply_stacks::generate, size, 0..seeds, supply_stacks::crane_variants()).map_err(|d| d.to_string()))
+        },
+        (2023, 4) => {
+            use crate::year_2023::day_4::scratchcards;
+            Some(differential::compare(scratchcards::generate, size, 0..seeds, scratchcards::copies_variants()).map_err(|d| d.to_string()))
+        },
+        (2023, 5) => {
+            use crate::year_2023::day_5::planting_seeds;
+            Some(differential::compare(planting_seeds::generate, size, 0..seeds, planting_seeds::range_location_variants()).map_err(|d| d.to_string()))
+        },
+        _ => None,
+    };
+    match outcome {
+        Some(Ok(compared)) => println!("All variants agree on {compared} generated inputs"),
+        Some(Err(divergence)) => println!("{divergence}"),
+        None => println!("Day {day} in {year} doesn't have variants to compare"),
+    }
+}
+
+fn render(year: u32, day: u8, path: String, output: &Path) {
+    if let Some(renderer) = get_renderer(year, day) {
+        let pictures: Result<Vec<(String, Canvas)>, PuzzleError> = renderer(read_problem_input_file(path.clone())).map_err(|e| e.in_puzzle(year, day).with_path(path));
+        let saved: Result<(), PuzzleError> = pictures.and_then(|pictures| pictures.into_iter().try_for_each(|(name, canvas)| {
+            let picture: PathBuf = picture_path(output, &name);
+            canvas.save(&picture).map(|_| println!("Wrote {}", picture.display()))
+        }));
+        if let Err(e) = saved {
+            eprintln!("{}", e.diagnostic());
+        }
+    } else {
+        println!("Rendering for day {day} in {year} doesn't exist");
+    }
+}
+
+fn run(args: Args) -> Result<(), PuzzleError> {
+    let year: u32 = args.require("year")?;
+    let day: u8 = args.require("day")?;
+    let path: String = args.get("input")?.unwrap_or(get_file_path(AnswerMode::Real, year, day));
+    match args.command {
+        Command::Solve => solve(year, day, path, Some(&args)),
+        Command::Animate => animate(year, day, path, Duration::from_millis(args.get("delay")?.unwrap_or(250))),
+        Command::Render => render(year, day, path, Path::new(&args.require::<String>("output")?)),
+        Command::Check => check(year, day, args.get("seeds")?.unwrap_or(100), args.get("size")?.unwrap_or(20)),
+        Command::Generate => generate(year, day, args.get("seed")?.unwrap_or(0), args.get("size")?.unwrap_or(100), args.get("output")?),
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        let year: u32 = get_year(stdin_reader());
+        let day: u8 = get_day(stdin_reader());
+        solve(year, day, get_file_path(AnswerMode::Real, year, day), None);
+    } else if let Err(e) = Args::parse(args).and_then(run) {
+        eprintln!("{}", e.diagnostic());
+    }
+}
+
 #[cfg(test)]
 mod main {
     use crate::*;
@@ -112,6 +280,31 @@ mod main {
         assert_eq!(read_problem_input_file(filepath).get(0), Some(&"1000".to_owned()));
     }
 
+    #[test]
+    fn every_solver_has_a_generator() {
+        for (year, days) in [(2022, 1..=6), (2023, 1..=11)] {
+            for day in days {
+                assert!(get_module(year, day).is_some() && get_generator(year, day).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn generated_inputs_are_solved() {
+        for (year, days) in [(2022, 1..=6), (2023, 1..=11)] {
+            for day in days {
+                let lines: Vec<String> = get_generator(year, day).unwrap()(&mut StdRng::seed_from_u64(0), 20);
+                assert_eq!(get_module(year, day).unwrap()(lines), Ok(()), "{year} day {day}");
+            }
+        }
+    }
+
+    #[test]
+    fn picture_path_names_each_picture() {
+        assert_eq!(picture_path(Path::new("out/maze.svg"), ""), PathBuf::from("out/maze.svg"));
+        assert_eq!(picture_path(Path::new("out/image.png"), "after"), PathBuf::from("out/image-after.png"));
+    }
+
     #[test]
     fn read_input_newline() {
         let filepath: String = "src/mocks/problem_input.txt".to_owned();
@@ -0,0 +1,160 @@
+use std::{fs, path::Path};
+
+use crate::PuzzleError;
+
+/// Side of a single grid cell, in SVG units and in PNG pixels.
+const CELL_SIZE: usize = 12;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Colour(pub u8, pub u8, pub u8);
+
+impl Colour {
+    pub const WHITE: Colour = Colour(255, 255, 255);
+    pub const BLACK: Colour = Colour(0, 0, 0);
+
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+/// How a single cell is drawn. The glyph is only drawn in SVG, PNGs show the fill alone.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CellStyle {
+    pub fill: Colour,
+    pub glyph: Option<char>,
+    pub ink: Colour,
+}
+
+impl CellStyle {
+    pub fn new(fill: Colour, glyph: char) -> Self {
+        CellStyle { fill, glyph: Some(glyph).filter(|glyph| !glyph.is_whitespace()), ink: Colour::BLACK }
+    }
+}
+
+/// A grid of styled cells, ready to be written out as an image.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Canvas {
+    rows: usize,
+    columns: usize,
+    cells: Vec<CellStyle>,
+}
+
+impl Canvas {
+    /// Styles every cell of `grid` with `style`, which gets the (row, column) and the character of the cell.
+    /// Short rows are padded with blank white cells.
+    pub fn new(grid: &[String], style: impl Fn((usize, usize), char) -> CellStyle) -> Self {
+        let rows: usize = grid.len();
+        let columns: usize = grid.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let cells: Vec<CellStyle> = grid.iter().enumerate().flat_map(|(row_n, row)| {
+            let padding = std::iter::repeat_n(CellStyle::new(Colour::WHITE, ' '), columns - row.chars().count());
+            row.chars().enumerate().map(|(column_n, c)| style((row_n, column_n), c)).collect::<Vec<CellStyle>>().into_iter().chain(padding)
+        }).collect();
+        Canvas { rows, columns, cells }
+    }
+
+    pub fn cell(&self, row: usize, column: usize) -> &CellStyle {
+        &self.cells[row * self.columns + column]
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (width, height) = (self.columns * CELL_SIZE, self.rows * CELL_SIZE);
+        let mut svg: Vec<String> = vec![
+            format!(r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#),
+            format!(r#"<g font-family="monospace" font-size="{}" text-anchor="middle" dominant-baseline="central">"#, CELL_SIZE * 3 / 4),
+        ];
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let cell: &CellStyle = self.cell(row, column);
+                let (x, y) = (column * CELL_SIZE, row * CELL_SIZE);
+                svg.push(format!(r#"<rect x="{x}" y="{y}" width="{CELL_SIZE}" height="{CELL_SIZE}" fill="{}"/>"#, cell.fill.hex()));
+                if let Some(glyph) = cell.glyph {
+                    let glyph: String = match glyph {
+                        '&' => "&amp;".to_string(),
+                        '<' => "&lt;".to_string(),
+                        '>' => "&gt;".to_string(),
+                        _ => glyph.to_string(),
+                    };
+                    svg.push(format!(r#"<text x="{}" y="{}" fill="{}">{glyph}</text>"#, x + CELL_SIZE / 2, y + CELL_SIZE / 2, cell.ink.hex()));
+                }
+            }
+        }
+        svg.extend(["</g>".to_string(), "</svg>".to_string()]);
+        svg.join("\n")
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, PuzzleError> {
+        let (width, height) = (self.columns * CELL_SIZE, self.rows * CELL_SIZE);
+        let pixels: Vec<u8> = (0..height).flat_map(|y| (0..width).flat_map(move |x| {
+            let Colour(r, g, b) = self.cell(y / CELL_SIZE, x / CELL_SIZE).fill;
+            [r, g, b]
+        })).collect();
+        let mut png: Vec<u8> = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let encoding_error = |e: png::EncodingError| PuzzleError::execution(format!("Could not encode the PNG: {e}"));
+        let mut writer = encoder.write_header().map_err(encoding_error)?;
+        writer.write_image_data(&pixels).map_err(encoding_error)?;
+        writer.finish().map_err(encoding_error)?;
+        Ok(png)
+    }
+
+    /// Writes the canvas as SVG or PNG, depending on the extension of `path`.
+    pub fn save(&self, path: &Path) -> Result<(), PuzzleError> {
+        let contents: Vec<u8> = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => self.to_svg().into_bytes(),
+            Some("png") => self.to_png()?,
+            _ => return Err(PuzzleError::validation(format!("Cannot render to {}, expected a .svg or .png file", path.display()))),
+        };
+        fs::write(path, contents).map_err(|e| PuzzleError::execution(format!("Could not write {}: {e}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::render::*;
+
+    const RED: Colour = Colour(255, 0, 0);
+
+    fn canvas() -> Canvas {
+        let grid: Vec<String> = vec!["#.".to_string(), "&".to_string()];
+        Canvas::new(&grid, |_, c| CellStyle::new(if c == '#' { RED } else { Colour::WHITE }, c))
+    }
+
+    #[test]
+    fn short_rows_are_padded() {
+        let canvas: Canvas = canvas();
+        assert_eq!((canvas.rows, canvas.columns), (2, 2));
+        assert_eq!(canvas.cell(1, 1), &CellStyle { fill: Colour::WHITE, glyph: None, ink: Colour::BLACK });
+    }
+
+    #[test]
+    fn style_gets_position_and_character() {
+        let canvas: Canvas = Canvas::new(&["ab".to_string()], |(row, column), c| CellStyle::new(Colour(row as u8, column as u8, 0), c));
+        assert_eq!(canvas.cell(0, 1), &CellStyle::new(Colour(0, 1, 0), 'b'));
+    }
+
+    #[test]
+    fn svg_has_a_rect_per_cell_and_escaped_glyphs() {
+        let svg: String = canvas().to_svg();
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains(r##"fill="#ff0000""##));
+        assert!(svg.contains(">&amp;</text>"));
+    }
+
+    #[test]
+    fn png_round_trips_fill_colours() {
+        let png: Vec<u8> = canvas().to_png().unwrap();
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap();
+        let mut pixels: Vec<u8> = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (2 * CELL_SIZE as u32, 2 * CELL_SIZE as u32));
+        assert_eq!(pixels[..3], [255, 0, 0]);
+        assert_eq!(pixels[3 * CELL_SIZE..3 * CELL_SIZE + 3], [255, 255, 255]);
+    }
+
+    #[test]
+    fn unknown_extension_is_rejected() {
+        assert!(canvas().save(Path::new("grid.bmp")).is_err());
+    }
+}
@@ -1,46 +1,142 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
 use itertools::Itertools;
+use rand::{rngs::StdRng, RngExt};
+
+use crate::error::{parse_lines, PuzzleError};
 
-fn group_calories(lines: Vec<String>) -> Vec<Vec<u32>> {
+fn group_calories(lines: Vec<String>) -> Result<Vec<Vec<u32>>, PuzzleError> {
+    let snacks: Vec<Option<u32>> = parse_lines(&lines, |line| match line.is_empty() {
+        true => Ok(None),
+        false => line.parse::<u32>().map(Some).map_err(|_| PuzzleError::parse(format!("Could not parse {line} into a calorie count"))),
+    })?;
     let mut calorie_groups: Vec<Vec<u32>> = Vec::new();
-    for (key, group) in &lines.into_iter().chunk_by(|line| !line.is_empty()) {
+    for (key, group) in &snacks.into_iter().chunk_by(|snack| snack.is_some()) {
         if key {
-            calorie_groups.push(group.map(|calorie| calorie.parse::<u32>().unwrap()).collect::<Vec<u32>>());
+            calorie_groups.push(group.flatten().collect::<Vec<u32>>());
         }
     }
-    calorie_groups
+    Ok(calorie_groups)
 }
 
 fn sum_calories(elf_groups: Vec<Vec<u32>>) -> Vec<u32> {
     elf_groups.into_iter().map(|group : Vec<u32>| group.into_iter().sum()).collect()
 }
 
+/// The `n` largest items, largest first, keeping at most `n` items in a min-heap instead of sorting everything.
+fn top_n<T: Ord>(items: impl IntoIterator<Item = T>, n: usize) -> Vec<T> {
+    let mut heap: BinaryHeap<Reverse<T>> = BinaryHeap::with_capacity(n + 1);
+    for item in items {
+        heap.push(Reverse(item));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+    heap.into_sorted_vec().into_iter().map(|Reverse(item)| item).collect()
+}
+
 fn largest_n_calories(total_calories: Vec<u32>, n: usize) -> Vec<u32> {
-    total_calories.into_iter().sorted_by(|a, b| Ord::cmp(&b, &a)).take(n).collect()
+    top_n(total_calories, n)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ElfTotal {
+    /// 0-based position of the elf in the input.
+    pub elf: usize,
+    pub calories: u32,
+}
+
+/// Calorie totals of every elf, in input order.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Inventory {
+    totals: Vec<ElfTotal>,
+}
+
+impl Inventory {
+    pub fn parse(lines: Vec<String>) -> Result<Self, PuzzleError> {
+        let totals: Vec<ElfTotal> = sum_calories(group_calories(lines)?).into_iter().enumerate().map(|(elf, calories)| ElfTotal { elf, calories }).collect();
+        Ok(Inventory { totals })
+    }
+
+    /// The `k` elves carrying the most calories, most first. Ties go to the elf that came first.
+    pub fn top(&self, k: usize) -> Vec<ElfTotal> {
+        top_n(self.totals.iter().map(|total| (total.calories, Reverse(total.elf))), k).into_iter()
+            .map(|(calories, Reverse(elf))| ElfTotal { elf, calories })
+            .collect()
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        match self.totals.len() {
+            0 => None,
+            n => Some(self.totals.iter().map(|total| total.calories as f64).sum::<f64>() / n as f64),
+        }
+    }
+
+    /// The calories of the elf at `rank` (0-based) when ordered from least to most, found by selection rather than sorting.
+    fn calories_at_rank(&self, rank: usize) -> u32 {
+        let mut calories: Vec<u32> = self.totals.iter().map(|total| total.calories).collect();
+        *calories.select_nth_unstable(rank).1
+    }
+
+    pub fn median(&self) -> Option<f64> {
+        match self.totals.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(self.calories_at_rank(n / 2) as f64),
+            n => Some((self.calories_at_rank(n / 2 - 1) as f64 + self.calories_at_rank(n / 2) as f64) / 2.0),
+        }
+    }
+
+    /// Nearest-rank percentile: the smallest total that at least `percent`% of elves carry no more than.
+    pub fn percentile(&self, percent: f64) -> Option<u32> {
+        if self.totals.is_empty() || !(0.0..=100.0).contains(&percent) {
+            return None;
+        }
+        let rank: usize = ((percent / 100.0 * self.totals.len() as f64).ceil() as usize).max(1);
+        Some(self.calories_at_rank(rank - 1))
+    }
 }
 
-pub fn solve(lines: Vec<String>) {
-    let calorie_sum: Vec<u32> = sum_calories(group_calories(lines));
-    let calorie_maximal = | n: usize | largest_n_calories(calorie_sum.clone(), n).into_iter().sum::<u32>();
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let inventory: Inventory = Inventory::parse(lines)?;
+    let calories: Vec<u32> = inventory.totals.iter().map(|total| total.calories).collect();
+    let calorie_maximal = | n: usize | largest_n_calories(calories.clone(), n).into_iter().sum::<u32>();
     println!("{}", calorie_maximal(1));
     println!("{}", calorie_maximal(3));
+    let leaders: Vec<String> = inventory.top(3).into_iter().map(|total| format!("elf {} ({})", total.elf + 1, total.calories)).collect();
+    println!("Carrying the most: {}", leaders.join(", "));
+    if let (Some(mean), Some(median), Some(percentile)) = (inventory.mean(), inventory.median(), inventory.percentile(90.0)) {
+        println!("Mean {mean:.1}, median {median:.1}, 90th percentile {percentile}");
+    }
+    Ok(())
+}
+
+/// Generates `size` elves, each carrying between one and fifteen snacks.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let elves: Vec<Vec<String>> = (0..size).map(|_| {
+        let snack_count: usize = rng.random_range(1..=15);
+        (0..snack_count).map(|_| rng.random_range(1000..=70000u32).to_string()).collect()
+    }).collect();
+    elves.join(&String::new())
 }
 
 #[cfg(test)]
 mod calorie_count {
     use crate::year_2022::day_1::calorie_count::largest_n_calories;
 
-    use super::{group_calories, sum_calories};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::{generate, group_calories, sum_calories, ElfTotal, Inventory};
 
     #[test]
     fn multi_group_calories() {
         let input = vec!["1000", "2000", "3000", "", "4000", "", "5000", "6000"].into_iter().map(|str| str.to_owned()).collect();
-        assert_eq!(group_calories(input), vec![vec![1000, 2000, 3000], vec![4000], vec![5000, 6000]]) 
+        assert_eq!(group_calories(input), Ok(vec![vec![1000, 2000, 3000], vec![4000], vec![5000, 6000]]))
     }
 
     #[test]
     fn no_group_calories() {
         let input: Vec<String> = vec![];
-        assert_eq!(group_calories(input), vec![] as Vec<Vec<u32>>); 
+        assert_eq!(group_calories(input), Ok(vec![] as Vec<Vec<u32>>));
     }
 
     #[test]
@@ -80,4 +176,62 @@ mod calorie_count {
         assert_eq!(largest_n_calories(vec![], 1), vec![]);
     }
 
+    #[test]
+    fn generated_elves_are_grouped() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(1), 20);
+        let groups: Vec<Vec<u32>> = group_calories(lines).unwrap();
+        assert_eq!(groups.len(), 20);
+        assert!(groups.iter().all(|group| (1..=15).contains(&group.len())));
+    }
+
+    fn inventory() -> Inventory {
+        let lines: Vec<String> = ["1000", "2000", "3000", "", "4000", "", "5000", "6000", "", "7000", "8000", "9000", "", "10000"].map(String::from).to_vec();
+        Inventory::parse(lines).unwrap()
+    }
+
+    #[test]
+    fn inventory_keeps_elf_indices() {
+        assert_eq!(inventory().totals[1], ElfTotal { elf: 1, calories: 4000 });
+    }
+
+    #[test]
+    fn inventory_top_k() {
+        assert_eq!(inventory().top(3), vec![ElfTotal { elf: 3, calories: 24000 }, ElfTotal { elf: 2, calories: 11000 }, ElfTotal { elf: 4, calories: 10000 }]);
+        assert_eq!(inventory().top(10).len(), 5);
+    }
+
+    #[test]
+    fn inventory_top_k_ties_favour_first_elf() {
+        let inventory: Inventory = Inventory::parse(["5", "", "7", "", "7"].map(String::from).to_vec()).unwrap();
+        assert_eq!(inventory.top(1), vec![ElfTotal { elf: 1, calories: 7 }]);
+    }
+
+    #[test]
+    fn inventory_statistics() {
+        let inventory: Inventory = inventory();
+        assert_eq!(inventory.mean(), Some(11000.0));
+        assert_eq!(inventory.median(), Some(10000.0));
+        assert_eq!(inventory.percentile(20.0), Some(4000));
+        assert_eq!(inventory.percentile(100.0), Some(24000));
+        assert_eq!(inventory.percentile(0.0), Some(4000));
+    }
+
+    #[test]
+    fn inventory_median_of_even_count() {
+        let inventory: Inventory = Inventory::parse(["1", "", "2", "", "4", "", "8"].map(String::from).to_vec()).unwrap();
+        assert_eq!(inventory.median(), Some(3.0));
+    }
+
+    #[test]
+    fn empty_inventory_has_no_statistics() {
+        let inventory: Inventory = Inventory::parse(vec![]).unwrap();
+        assert_eq!((inventory.mean(), inventory.median(), inventory.percentile(50.0)), (None, None, None));
+    }
+
+    #[test]
+    fn inventory_error_names_the_line() {
+        let err = Inventory::parse(["1000", "", "20O0"].map(String::from).to_vec()).unwrap_err();
+        assert_eq!(err.location().line, Some(3));
+        assert_eq!(err.location().text, Some("20O0".to_string()));
+    }
 }
@@ -0,0 +1,126 @@
+use itertools::Itertools;
+
+use crate::{error::parse_lines, PuzzleError};
+
+use super::{rps::{total_score, Round}, rules::{GameResult, Move, Rules}};
+
+/// What the second column of the strategy guide could mean: each symbol is either our move or the result we should aim for.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Interpretation {
+    Moves(Vec<(String, Move)>),
+    Results(Vec<(String, GameResult)>),
+}
+
+impl Interpretation {
+    fn round(&self, opponent: Move, symbol: &str, rules: &Rules) -> Round {
+        match self {
+            Interpretation::Moves(mapping) => {
+                let you: Move = mapping.iter().find(|(s, _)| s == symbol).expect("every symbol is mapped").1;
+                Round { opponent, you, result: GameResult::outcome(rules, opponent, you) }
+            },
+            Interpretation::Results(mapping) => {
+                let result: GameResult = mapping.iter().find(|(s, _)| s == symbol).expect("every symbol is mapped").1;
+                Round { opponent, you: result.move_from_outcome(rules, opponent), result }
+            },
+        }
+    }
+
+    pub fn describe(&self, rules: &Rules) -> String {
+        match self {
+            Interpretation::Moves(mapping) => mapping.iter().map(|(s, m)| format!("{s}={}", rules.name(*m))).join(" "),
+            Interpretation::Results(mapping) => mapping.iter().map(|(s, result)| format!("{s}={result:?}")).join(" "),
+        }
+    }
+}
+
+/// Every way of reading the second column as moves or as results, with the score each reading gives.
+/// Several symbols may stand for the same move or result, unless `distinct` asks for one each.
+pub fn score_interpretations(lines: &[String], rules: &Rules, distinct: bool) -> Result<Vec<(Interpretation, u32)>, PuzzleError> {
+    let guide: Vec<(Move, String)> = parse_lines(lines, |line| match line.split_whitespace().collect_tuple::<(&str, &str)>() {
+        _ if line.trim().is_empty() => Ok(None),
+        Some((opponent, symbol)) => match rules.parse_move(opponent) {
+            Some(opponent) => Ok(Some((opponent, symbol.to_string()))),
+            None => Err(PuzzleError::parse(format!("Could not parse {opponent} into the opponent's move"))),
+        },
+        None => Err(PuzzleError::parse(format!("Line was incorrectly formatted: could not split {line} into two by whitespace"))),
+    })?.into_iter().flatten().collect();
+    let symbols: Vec<String> = guide.iter().map(|(_, symbol)| symbol.clone()).unique().sorted().collect();
+    let all_moves: Vec<Move> = rules.moves().collect();
+    let moves = symbols.iter().map(|_| all_moves.iter().copied()).multi_cartesian_product()
+        .filter(|moves| !distinct || moves.iter().all_unique())
+        .map(|moves| Interpretation::Moves(symbols.iter().cloned().zip(moves).collect()));
+    let results = symbols.iter().map(|_| GameResult::ALL.into_iter()).multi_cartesian_product()
+        .filter(|results| !distinct || results.iter().all_unique())
+        .map(|results| Interpretation::Results(symbols.iter().cloned().zip(results).collect()));
+    Ok(moves.chain(results).map(|interpretation| {
+        let rounds: Vec<Round> = guide.iter().map(|(opponent, symbol)| interpretation.round(*opponent, symbol, rules)).collect();
+        let score: u32 = total_score(rounds, rules);
+        (interpretation, score)
+    }).collect())
+}
+
+/// The interpretations scoring exactly `target`, or those with the highest score when there is no target.
+pub fn best_interpretations(scored: Vec<(Interpretation, u32)>, target: Option<u32>) -> Vec<(Interpretation, u32)> {
+    let wanted: Option<u32> = target.or(scored.iter().map(|(_, score)| *score).max());
+    scored.into_iter().filter(|(_, score)| Some(*score) == wanted).collect()
+}
+
+#[cfg(test)]
+mod test_interpretations {
+    use super::*;
+
+    fn guide() -> Vec<String> {
+        vec!["A Y".to_string(), "B X".to_string(), "C Z".to_string()]
+    }
+
+    #[test]
+    fn every_mapping_is_scored() {
+        let scored: Vec<(Interpretation, u32)> = score_interpretations(&guide(), &Rules::classic(), false).unwrap();
+        // each of the three symbols can stand for any of the three moves, or any of the three results
+        assert_eq!(scored.len(), 27 + 27);
+        let rules: Rules = Rules::classic();
+        assert!(scored.iter().any(|(interpretation, _)| interpretation.describe(&rules) == "X=Rock Y=Rock Z=Paper"));
+        let puzzle_readings: Vec<u32> = scored.iter()
+            .filter(|(interpretation, _)| ["X=Rock Y=Paper Z=Scissors", "X=Loss Y=Draw Z=Win"].contains(&interpretation.describe(&rules).as_str()))
+            .map(|(_, score)| *score)
+            .collect();
+        assert_eq!(puzzle_readings, vec![15, 12]);
+    }
+
+    #[test]
+    fn distinct_mappings_only() {
+        let scored: Vec<(Interpretation, u32)> = score_interpretations(&guide(), &Rules::classic(), true).unwrap();
+        assert_eq!(scored.len(), 6 + 6);
+        let rules: Rules = Rules::classic();
+        let puzzle_readings: Vec<u32> = scored.iter()
+            .filter(|(interpretation, _)| ["X=Rock Y=Paper Z=Scissors", "X=Loss Y=Draw Z=Win"].contains(&interpretation.describe(&rules).as_str()))
+            .map(|(_, score)| *score)
+            .collect();
+        assert_eq!(puzzle_readings, vec![15, 12]);
+    }
+
+    #[test]
+    fn best_interpretation_wins_every_round() {
+        let rules: Rules = Rules::classic();
+        let best: Vec<(Interpretation, u32)> = best_interpretations(score_interpretations(&guide(), &rules, false).unwrap(), None);
+        // paper, scissors and rock win every round, as does aiming to win with every symbol
+        assert_eq!(best.iter().map(|(interpretation, score)| (interpretation.describe(&rules), *score)).collect::<Vec<_>>(), vec![
+            ("X=Scissors Y=Paper Z=Rock".to_string(), 24),
+            ("X=Win Y=Win Z=Win".to_string(), 24),
+        ]);
+    }
+
+    #[test]
+    fn interpretations_hitting_a_target() {
+        let rules: Rules = Rules::classic();
+        let hits: Vec<(Interpretation, u32)> = best_interpretations(score_interpretations(&guide(), &rules, false).unwrap(), Some(12));
+        assert!(hits.iter().any(|(interpretation, _)| interpretation.describe(&rules) == "X=Loss Y=Draw Z=Win"));
+        assert!(hits.iter().all(|(_, score)| *score == 12));
+    }
+
+    #[test]
+    fn unknown_opponent_move_names_the_line() {
+        let lines: Vec<String> = vec!["A Y".to_string(), "".to_string(), "Q X".to_string()];
+        assert_eq!(score_interpretations(&lines, &Rules::classic(), false).unwrap_err().location().line, Some(3));
+    }
+}
@@ -1 +1,4 @@
+pub mod interpretations;
 pub mod rps;
+pub mod rules;
+pub mod tournament;
@@ -1,134 +1,52 @@
-use std::str::FromStr;
+use std::path::Path;
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::IndexedRandom};
 
-use crate::ParseInputError;
+use crate::{cli::Args, PuzzleError};
 
-#[derive(Debug, PartialEq, Eq)]
-struct ParseMoveError;
+use super::{
+    interpretations::{best_interpretations, score_interpretations, Interpretation},
+    rules::{GameResult, Move, Rules},
+    tournament::{round_robin, Fixed, FrequencyCounter, League, PatternMatcher, Random, Sequence, Strategy},
+};
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Move {
-    Rock = 1,
-    Paper = 2,
-    Scissors = 3
-}
-
-impl Move {
-    fn weakness(&self) -> Self {
-        match self {
-            Move::Rock => Move::Paper,
-            Move::Paper => Move::Scissors,
-            Move::Scissors => Move::Rock,
-        }
-    }
-
-    fn strength(&self) -> Self {
-        match self {
-            Move::Rock => Move::Scissors,
-            Move::Paper => Move::Rock,
-            Move::Scissors => Move::Paper,
-        }
-    }
-
-    fn parse(s: &str) -> Option<Self> {
-        s.parse::<Move>().ok()
-    }
-}
-
-impl FromStr for Move {
-    type Err = ParseMoveError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "A" | "X" => Ok(Move::Rock),
-            "B" | "Y" => Ok(Move::Paper),
-            "C" | "Z" => Ok(Move::Scissors),
-            _ => Err(ParseMoveError)
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum GameResult {
-    Loss = 0,
-    Win = 6,
-    Draw = 3,
-}
-
-impl FromStr for GameResult {
-    type Err = ParseMoveError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "X" => Ok(GameResult::Loss),
-            "Y" => Ok(GameResult::Draw),
-            "Z" => Ok(GameResult::Win),
-            _ => Err(ParseMoveError)
-        }
-    }
-}
-
-impl GameResult {
-    fn outcome(opponent_move: Move, your_move: Move) -> Self {
-        if opponent_move == your_move {
-            return GameResult::Draw;
-        }
-        if opponent_move.weakness() == your_move {
-            return GameResult::Win;
-        }
-        GameResult::Loss
-    }
-    
-    fn move_from_outcome(&self, opponent_move: Move) -> Move {
-        match self {
-            GameResult::Draw => opponent_move,
-            GameResult::Win => opponent_move.weakness(), 
-            GameResult::Loss => opponent_move.strength()
-        }
-    }
-
-    fn parse(s: &str) -> Option<Self> {
-        s.parse::<GameResult>().ok()
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-struct Round {
-    opponent: Move,
-    you: Move,
-    result: GameResult,
+pub(super) struct Round {
+    pub(super) opponent: Move,
+    pub(super) you: Move,
+    pub(super) result: GameResult,
 }
 
 impl Round {
-    fn new_from_moves(line: &str) -> Option<Self> {
-        match parse_line(line, Move::parse, Move::parse).ok() {
-            Some((l, r)) => Some(Round{opponent: l, you: r, result: GameResult::outcome(l, r)}),
+    fn new_from_moves(line: &str, rules: &Rules) -> Option<Self> {
+        match parse_line(line, |s| rules.parse_move(s), |s| rules.parse_move(s)).ok() {
+            Some((l, r)) => Some(Round{opponent: l, you: r, result: GameResult::outcome(rules, l, r)}),
             _ => None,    
         }
     }
 
-    fn new_from_result(line: &str) -> Option<Self> {
-        match parse_line(line, Move::parse, GameResult::parse).ok() {
-            Some((l, r)) => Some(Round{opponent: l, you: r.move_from_outcome(l), result: r}),
+    fn new_from_result(line: &str, rules: &Rules) -> Option<Self> {
+        match parse_line(line, |s| rules.parse_move(s), |s| rules.parse_result(s)).ok() {
+            Some((l, r)) => Some(Round{opponent: l, you: r.move_from_outcome(rules, l), result: r}),
             _ => None,    
         }
     } 
 
-    fn my_score(&self) -> u32 {
-        (self.result as u32) + (self.you as u32)
+    fn my_score(&self, rules: &Rules) -> u32 {
+        rules.score(self.you, self.result)
     }
 }
 
-fn parse_line<L, R>(line: &str, parse_left: impl Fn(&str) -> Option<L>, parse_right: impl Fn(&str) -> Option<R>) -> Result<(L, R), ParseInputError> {
+fn parse_line<L, R>(line: &str, parse_left: impl Fn(&str) -> Option<L>, parse_right: impl Fn(&str) -> Option<R>) -> Result<(L, R), PuzzleError> {
     match line.split_whitespace().collect_tuple::<(&str, &str)>() {
         Some((left_token, right_token)) => {
             match (parse_left(left_token), parse_right(right_token)) {
                 (Some(x), Some(y)) => Ok((x, y)),
-                _ => Err(ParseInputError {details: "Failed to parse tokens".to_string()}),
+                _ => Err(PuzzleError::parse("Failed to parse tokens")),
             }
         },
-        _ => Err(ParseInputError {details: format!("Line was incorrectly formatted: could not split {} into two by whitespace", line)}),
+        _ => Err(PuzzleError::parse(format!("Line was incorrectly formatted: could not split {} into two by whitespace", line))),
     }
 } 
 
@@ -136,116 +54,212 @@ fn parse_rounds(lines: &Vec<String>, parse_round: impl Fn(&str) -> Option<Round>
     lines.into_iter().filter_map(|line| parse_round(&line[..])).collect()
 }
 
-fn total_score(rounds: Vec<Round>) -> u32 {
-    rounds.into_iter().map(|round| round.my_score()).sum::<u32>()
+pub(super) fn total_score(rounds: Vec<Round>, rules: &Rules) -> u32 {
+    rounds.into_iter().map(|round| round.my_score(rules)).sum::<u32>()
+}
+
+fn solve_with_rules(lines: Vec<String>, rules: &Rules) -> Result<(), PuzzleError> {
+    println!("Total Score when moves are given: {}", total_score(parse_rounds(&lines, |line| Round::new_from_moves(line, rules)), rules));
+    println!("Total Score when moves are given: {}", total_score(parse_rounds(&lines, |line| Round::new_from_result(line, rules)), rules));
+    Ok(())
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    solve_with_rules(lines, &Rules::classic())
+}
+
+/// Plays the strategy guide by the rules in the file given with `--rules`, rock-paper-scissors otherwise.
+/// With `--interpret` every reading of the second column is scored instead, listing the readings that
+/// reach `--target` or, without a target, the best scoring ones; `--distinct` only considers readings
+/// where every symbol stands for a different move or result. With `--tournament` the moves of the
+/// guide play `--rounds` rounds against every bot, and the bots against each other.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    let rules: Rules = match args.get::<String>("rules")? {
+        Some(path) => Rules::load(Path::new(&path))?,
+        None => Rules::classic(),
+    };
+    if args.has("tournament") {
+        let guide: Vec<Move> = parse_rounds(&lines, |line| Round::new_from_moves(line, &rules)).into_iter().map(|round| round.you).collect();
+        let mut bots: Vec<Box<dyn Strategy>> = vec![
+            Box::new(Fixed(rules.moves().next().unwrap())),
+            Box::new(Random::new(args.get("seed")?.unwrap_or(0))),
+            Box::new(FrequencyCounter),
+            Box::new(PatternMatcher { length: 3 }),
+        ];
+        if !guide.is_empty() {
+            bots.insert(0, Box::new(Sequence { name: "strategy guide".to_string(), moves: guide }));
+        }
+        println!("{}", League(round_robin(&mut bots, &rules, args.get("rounds")?.unwrap_or(1000))));
+        return Ok(());
+    }
+    if !args.has("interpret") {
+        return solve_with_rules(lines, &rules);
+    }
+    let scored: Vec<(Interpretation, u32)> = score_interpretations(&lines, &rules, args.has("distinct"))?;
+    let considered: usize = scored.len();
+    let target: Option<u32> = args.get("target")?;
+    let best: Vec<(Interpretation, u32)> = best_interpretations(scored, target);
+    match (target, best.first()) {
+        (Some(target), _) => println!("{} of {considered} interpretations score {target}", best.len()),
+        (None, Some((_, score))) => println!("{} of {considered} interpretations score the most, {score}", best.len()),
+        (None, None) => println!("No interpretations to score"),
+    }
+    for (interpretation, _) in best {
+        println!("  {}", interpretation.describe(&rules));
+    }
+    Ok(())
 }
 
-pub fn solve(lines: Vec<String>) {
-    println!("Total Score when moves are given: {}", total_score(parse_rounds(&lines, Round::new_from_moves)));
-    println!("Total Score when moves are given: {}", total_score(parse_rounds(&lines, Round::new_from_result)));
+/// Generates a strategy guide of `size` rounds.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    (0..size).map(|_| format!("{} {}", ["A", "B", "C"].choose(rng).unwrap(), ["X", "Y", "Z"].choose(rng).unwrap())).collect()
 }
 
 #[cfg(test)]
 mod rps {
+    use rand::SeedableRng;
+
     use super::*;
 
+    fn parse_move(s: &str) -> Option<Move> {
+        Rules::classic().parse_move(s)
+    }
+
+    fn parse_result(s: &str) -> Option<GameResult> {
+        Rules::classic().parse_result(s)
+    }
+
     #[test]
     fn test_parse_move() {
-        assert_eq!("A".parse::<Move>(), Ok(Move::Rock));
-        assert_eq!("B".parse::<Move>(), Ok(Move::Paper));
-        assert_eq!("C".parse::<Move>(), Ok(Move::Scissors));
-
-        assert_eq!("X".parse::<Move>(), Ok(Move::Rock));
-        assert_eq!("Y".parse::<Move>(), Ok(Move::Paper));
-        assert_eq!("Z".parse::<Move>(), Ok(Move::Scissors));
+        let rules: Rules = Rules::classic();
+        assert_eq!(rules.parse_move("A"), Some(Move::ROCK));
+        assert_eq!(rules.parse_move("B"), Some(Move::PAPER));
+        assert_eq!(rules.parse_move("C"), Some(Move::SCISSORS));
+
+        assert_eq!(rules.parse_move("X"), Some(Move::ROCK));
+        assert_eq!(rules.parse_move("Y"), Some(Move::PAPER));
+        assert_eq!(rules.parse_move("Z"), Some(Move::SCISSORS));
     }
 
     #[test]
     fn test_move_weakness() {
-        assert_eq!(Move::Rock.weakness(), Move::Paper);
-        assert_eq!(Move::Paper.weakness(), Move::Scissors);
-        assert_eq!(Move::Scissors.weakness(), Move::Rock);
+        let rules: Rules = Rules::classic();
+        assert_eq!(rules.weaknesses(Move::ROCK), vec![Move::PAPER]);
+        assert_eq!(rules.weaknesses(Move::PAPER), vec![Move::SCISSORS]);
+        assert_eq!(rules.weaknesses(Move::SCISSORS), vec![Move::ROCK]);
     }
 
     #[test]
     fn test_move_strength() {
-        assert_eq!(Move::Rock.strength(), Move::Scissors);
-        assert_eq!(Move::Paper.strength(), Move::Rock);
-        assert_eq!(Move::Scissors.strength(), Move::Paper);
+        let rules: Rules = Rules::classic();
+        assert_eq!(rules.strengths(Move::ROCK), vec![Move::SCISSORS]);
+        assert_eq!(rules.strengths(Move::PAPER), vec![Move::ROCK]);
+        assert_eq!(rules.strengths(Move::SCISSORS), vec![Move::PAPER]);
     }
 
     #[test]
     fn test_parse_game_result() {
-        assert_eq!("X".parse::<GameResult>(), Ok(GameResult::Loss));
-        assert_eq!("Y".parse::<GameResult>(), Ok(GameResult::Draw));
-        assert_eq!("Z".parse::<GameResult>(), Ok(GameResult::Win));
+        let rules: Rules = Rules::classic();
+        assert_eq!(rules.parse_result("X"), Some(GameResult::Loss));
+        assert_eq!(rules.parse_result("Y"), Some(GameResult::Draw));
+        assert_eq!(rules.parse_result("Z"), Some(GameResult::Win));
     }
 
     #[test]
     fn test_get_result() {
-        assert_eq!(GameResult::outcome(Move::Scissors, Move::Rock), GameResult::Win);
-        assert_eq!(GameResult::outcome(Move::Scissors, Move::Paper), GameResult::Loss);
-        assert_eq!(GameResult::outcome(Move::Scissors, Move::Scissors), GameResult::Draw);
+        let rules: Rules = Rules::classic();
+        assert_eq!(GameResult::outcome(&rules, Move::SCISSORS, Move::ROCK), GameResult::Win);
+        assert_eq!(GameResult::outcome(&rules, Move::SCISSORS, Move::PAPER), GameResult::Loss);
+        assert_eq!(GameResult::outcome(&rules, Move::SCISSORS, Move::SCISSORS), GameResult::Draw);
     }
 
     #[test]
     fn test_get_result_from_move() {
-        assert_eq!(GameResult::Win.move_from_outcome(Move::Rock), Move::Paper);
-        assert_eq!(GameResult::Loss.move_from_outcome(Move::Rock), Move::Scissors);
-        assert_eq!(GameResult::Draw.move_from_outcome(Move::Rock), Move::Rock);
+        let rules: Rules = Rules::classic();
+        assert_eq!(GameResult::Win.move_from_outcome(&rules, Move::ROCK), Move::PAPER);
+        assert_eq!(GameResult::Loss.move_from_outcome(&rules, Move::ROCK), Move::SCISSORS);
+        assert_eq!(GameResult::Draw.move_from_outcome(&rules, Move::ROCK), Move::ROCK);
     }
 
     #[test]
     fn test_parse_line() {
-        assert_eq!(parse_line("A Y", Move::parse, Move::parse), Ok((Move::Rock, Move::Paper)));
-        assert_eq!(parse_line("A Y", Move::parse, GameResult::parse), Ok((Move::Rock, GameResult::Draw)));
+        assert_eq!(parse_line("A Y", parse_move, parse_move), Ok((Move::ROCK, Move::PAPER)));
+        assert_eq!(parse_line("A Y", parse_move, parse_result), Ok((Move::ROCK, GameResult::Draw)));
     }
 
     #[test]
     fn parse_line_too_many_tokens() {
         let line = "A Y Z";
-        assert!(parse_line(line, Move::parse, Move::parse).err().is_some());
+        assert!(parse_line(line, parse_move, parse_move).err().is_some());
     }
 
     #[test]
     fn parse_line_wrong_tokens() {
         let line = "A D";
-        assert!(parse_line(line, Move::parse, Move::parse).err().is_some());
+        assert!(parse_line(line, parse_move, parse_move).err().is_some());
     }
 
     #[test]
     fn parse_line_failure_causes_round_parsing_fail() {
         // Ensure that parse line fails
-        assert!(parse_line("A Y Z", Move::parse, Move::parse).err().is_some());
+        assert!(parse_line("A Y Z", parse_move, parse_move).err().is_some());
         
-        assert!(Round::new_from_moves("A Y Z").is_none());
-        assert!(Round::new_from_result("A Y Z").is_none());
+        assert!(Round::new_from_moves("A Y Z", &Rules::classic()).is_none());
+        assert!(Round::new_from_result("A Y Z", &Rules::classic()).is_none());
     }
 
     #[test]
     fn test_parse_from_moves() {
-        assert_eq!(Round::new_from_moves("A Y"), Some(Round{opponent: Move::Rock, you: Move::Paper, result: GameResult::Win}));
-        assert_eq!(Round::new_from_moves("B X"), Some(Round{opponent: Move::Paper, you: Move::Rock, result: GameResult::Loss}));
-        assert_eq!(Round::new_from_moves("C Z"), Some(Round{opponent: Move::Scissors, you: Move::Scissors, result: GameResult::Draw}));
+        assert_eq!(Round::new_from_moves("A Y", &Rules::classic()), Some(Round{opponent: Move::ROCK, you: Move::PAPER, result: GameResult::Win}));
+        assert_eq!(Round::new_from_moves("B X", &Rules::classic()), Some(Round{opponent: Move::PAPER, you: Move::ROCK, result: GameResult::Loss}));
+        assert_eq!(Round::new_from_moves("C Z", &Rules::classic()), Some(Round{opponent: Move::SCISSORS, you: Move::SCISSORS, result: GameResult::Draw}));
     }
 
     #[test]
     fn test_parse_from_result() {
-        assert_eq!(Round::new_from_result("A Y"), Some(Round{opponent: Move::Rock, you: Move::Rock, result: GameResult::Draw}));
-        assert_eq!(Round::new_from_result("B X"), Some(Round{opponent: Move::Paper, you: Move::Rock, result: GameResult::Loss}));
-        assert_eq!(Round::new_from_result("C Z"), Some(Round{opponent: Move::Scissors, you: Move::Rock, result: GameResult::Win}));
+        assert_eq!(Round::new_from_result("A Y", &Rules::classic()), Some(Round{opponent: Move::ROCK, you: Move::ROCK, result: GameResult::Draw}));
+        assert_eq!(Round::new_from_result("B X", &Rules::classic()), Some(Round{opponent: Move::PAPER, you: Move::ROCK, result: GameResult::Loss}));
+        assert_eq!(Round::new_from_result("C Z", &Rules::classic()), Some(Round{opponent: Move::SCISSORS, you: Move::ROCK, result: GameResult::Win}));
     }
 
     #[test]
     fn test_scoring() {
         let rounds: Vec<Round> = vec![
-            Round{opponent: Move::Rock, you: Move::Paper, result: GameResult::Win}, 
-            Round{opponent: Move::Paper, you: Move::Rock, result: GameResult::Loss},
-            Round{opponent: Move::Scissors, you: Move::Scissors, result: GameResult::Draw}
+            Round{opponent: Move::ROCK, you: Move::PAPER, result: GameResult::Win}, 
+            Round{opponent: Move::PAPER, you: Move::ROCK, result: GameResult::Loss},
+            Round{opponent: Move::SCISSORS, you: Move::SCISSORS, result: GameResult::Draw}
         ];
-        assert_eq!(rounds.get(0).unwrap().my_score(), 8);
-        assert_eq!(rounds.get(1).unwrap().my_score(), 1);
-        assert_eq!(rounds.get(2).unwrap().my_score(), 6);
-        assert_eq!(total_score(rounds), 8 + 1 + 6);
+        let rules: Rules = Rules::classic();
+        assert_eq!(rounds.first().unwrap().my_score(&rules), 8);
+        assert_eq!(rounds.get(1).unwrap().my_score(&rules), 1);
+        assert_eq!(rounds.get(2).unwrap().my_score(&rules), 6);
+        assert_eq!(total_score(rounds, &rules), 8 + 1 + 6);
+    }
+
+    #[test]
+    fn test_generated_rounds_parse() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(2), 50);
+        let rules: Rules = Rules::classic();
+        assert_eq!(parse_rounds(&lines, |line| Round::new_from_moves(line, &rules)).len(), 50);
+        assert_eq!(parse_rounds(&lines, |line| Round::new_from_result(line, &rules)).len(), 50);
+    }
+
+    #[test]
+    fn test_score_with_rpsls_rules() {
+        let rules: Rules = [
+            "moves: Scissors Paper Rock Lizard Spock",
+            "Scissors beats Paper Lizard",
+            "Paper beats Rock Spock",
+            "Rock beats Lizard Scissors",
+            "Lizard beats Spock Paper",
+            "Spock beats Scissors Rock",
+            "symbols: A=Scissors B=Paper C=Rock D=Lizard E=Spock",
+            "outcomes: L=Loss D=Draw W=Win",
+        ].join("\n").parse().unwrap();
+        let lines: Vec<String> = vec!["E D".to_string(), "A C".to_string()];
+        // lizard poisons spock, rock crushes scissors
+        assert_eq!(total_score(parse_rounds(&lines, |line| Round::new_from_moves(line, &rules)), &rules), (4 + 6) + (3 + 6));
+        // spock beats scissors and rock, and scissors is listed first
+        assert_eq!(Round::new_from_result("E L", &rules).map(|round| round.you), Some(Move(0)));
     }
 }
@@ -0,0 +1,292 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use crate::PuzzleError;
+
+/// A move, as its position in the list of moves of the rules it belongs to.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash, PartialOrd, Ord)]
+pub struct Move(pub usize);
+
+/// The moves of the classic rules.
+#[cfg(test)]
+impl Move {
+    pub const ROCK: Move = Move(0);
+    pub const PAPER: Move = Move(1);
+    pub const SCISSORS: Move = Move(2);
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+pub enum GameResult {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl FromStr for GameResult {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Loss" => Ok(GameResult::Loss),
+            "Draw" => Ok(GameResult::Draw),
+            "Win" => Ok(GameResult::Win),
+            _ => Err(PuzzleError::parse(format!("Could not parse {s} into a game result, expected Loss, Draw or Win"))),
+        }
+    }
+}
+
+impl GameResult {
+    pub const ALL: [GameResult; 3] = [GameResult::Loss, GameResult::Draw, GameResult::Win];
+
+    pub fn outcome(rules: &Rules, opponent_move: Move, your_move: Move) -> Self {
+        if opponent_move == your_move {
+            return GameResult::Draw;
+        }
+        if rules.beats(your_move, opponent_move) {
+            return GameResult::Win;
+        }
+        GameResult::Loss
+    }
+
+    /// The move giving this result against `opponent_move`. When several moves would do, the one listed first in the rules is played.
+    pub fn move_from_outcome(&self, rules: &Rules, opponent_move: Move) -> Move {
+        match self {
+            GameResult::Draw => opponent_move,
+            GameResult::Win => rules.weaknesses(opponent_move)[0],
+            GameResult::Loss => rules.strengths(opponent_move)[0],
+        }
+    }
+}
+
+/// Rock-paper-scissors as a rules file. Each move beats the moves listed after `beats`; without
+/// any `beats` lines every move beats the (n - 1) / 2 moves listed before it, wrapping around.
+pub const CLASSIC: &str = "\
+moves: Rock Paper Scissors
+symbols: A=Rock B=Paper C=Scissors X=Rock Y=Paper Z=Scissors
+outcomes: X=Loss Y=Draw Z=Win
+scores: Rock=1 Paper=2 Scissors=3 Loss=0 Draw=3 Win=6";
+
+/// The rules of a cyclic hand game: an odd number of moves where every move beats exactly half of the others.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Rules {
+    names: Vec<String>,
+    /// `beats[a][b]` when move `a` beats move `b`
+    beats: Vec<Vec<bool>>,
+    symbols: HashMap<String, Move>,
+    outcomes: HashMap<String, GameResult>,
+    move_scores: Vec<u32>,
+    result_scores: HashMap<GameResult, u32>,
+}
+
+impl Rules {
+    pub fn classic() -> Self {
+        CLASSIC.parse().expect("the classic rules are valid")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PuzzleError> {
+        let contents: String = fs::read_to_string(path).map_err(|e| PuzzleError::execution(format!("Could not read the rules file {}: {e}", path.display())))?;
+        contents.parse::<Rules>().map_err(|e| e.with_path(path.display().to_string()))
+    }
+
+    pub fn moves(&self) -> impl DoubleEndedIterator<Item = Move> {
+        (0..self.names.len()).map(Move)
+    }
+
+    pub fn name(&self, m: Move) -> &str {
+        &self.names[m.0]
+    }
+
+    pub fn beats(&self, a: Move, b: Move) -> bool {
+        self.beats[a.0][b.0]
+    }
+
+    /// The moves that beat `m`, in the order of the rules.
+    pub fn weaknesses(&self, m: Move) -> Vec<Move> {
+        self.moves().filter(|other| self.beats(*other, m)).collect()
+    }
+
+    /// The moves that `m` beats, in the order of the rules.
+    pub fn strengths(&self, m: Move) -> Vec<Move> {
+        self.moves().filter(|other| self.beats(m, *other)).collect()
+    }
+
+    pub fn parse_move(&self, symbol: &str) -> Option<Move> {
+        self.symbols.get(symbol).copied()
+    }
+
+    pub fn parse_result(&self, symbol: &str) -> Option<GameResult> {
+        self.outcomes.get(symbol).copied()
+    }
+
+    pub fn score(&self, you: Move, result: GameResult) -> u32 {
+        self.move_scores[you.0] + self.result_scores[&result]
+    }
+
+    fn find_move(&self, name: &str) -> Result<Move, PuzzleError> {
+        self.names.iter().position(|n| n == name).map(Move).ok_or(PuzzleError::validation(format!("Unknown move {name}")))
+    }
+
+    /// Every move must beat exactly half of the other moves, and lose to the rest.
+    fn validate(&self) -> Result<(), PuzzleError> {
+        let n: usize = self.names.len();
+        for a in self.moves() {
+            if self.beats(a, a) {
+                return Err(PuzzleError::validation(format!("{} cannot beat itself", self.name(a))));
+            }
+            if let Some(b) = self.moves().find(|b| *b != a && self.beats(a, *b) == self.beats(*b, a)) {
+                return Err(PuzzleError::validation(format!("Exactly one of {} and {} must beat the other", self.name(a), self.name(b))));
+            }
+            if self.strengths(a).len() != (n - 1) / 2 {
+                return Err(PuzzleError::validation(format!("{} beats {} moves, but every move must beat {}", self.name(a), self.strengths(a).len(), (n - 1) / 2)));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn assignments(s: &str) -> impl Iterator<Item = Result<(&str, &str), PuzzleError>> {
+    s.split_whitespace().map(|pair| pair.split_once('=').ok_or(PuzzleError::parse(format!("Expected name=value, found {pair}"))))
+}
+
+impl FromStr for Rules {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<(usize, &str)> = s.lines().map(str::trim).enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| (i + 1, line))
+            .collect();
+        let (moves_line, moves) = lines.iter().find_map(|(i, line)| line.strip_prefix("moves:").map(|moves| (*i, moves)))
+            .ok_or(PuzzleError::parse("Rules must list the moves on a line starting with moves:"))?;
+        let names: Vec<String> = moves.split_whitespace().map(String::from).collect();
+        let n: usize = names.len();
+        if n < 3 || n.is_multiple_of(2) {
+            return Err(PuzzleError::validation(format!("Expected an odd number of moves, at least 3, found {n}")).at_line(moves_line));
+        }
+        if let Some(name) = names.iter().enumerate().find_map(|(i, name)| names[..i].contains(name).then_some(name)) {
+            return Err(PuzzleError::validation(format!("Move {name} is listed twice")).at_line(moves_line));
+        }
+        let mut rules: Rules = Rules {
+            // move i beats the (n - 1) / 2 moves before it
+            beats: (0..n).map(|a| (0..n).map(|b| (1..=(n - 1) / 2).any(|k| (a + n - k) % n == b)).collect()).collect(),
+            names,
+            symbols: HashMap::new(),
+            outcomes: HashMap::new(),
+            move_scores: (1..=n as u32).collect(),
+            result_scores: HashMap::from([(GameResult::Loss, 0), (GameResult::Draw, 3), (GameResult::Win, 6)]),
+        };
+        let mut custom_beats: bool = false;
+        for (i, line) in lines.iter().copied().filter(|(i, _)| *i != moves_line) {
+            let tagged = |e: PuzzleError| e.at_line(i).with_text(line);
+            if let Some(symbols) = line.strip_prefix("symbols:") {
+                for pair in assignments(symbols) {
+                    let (symbol, name) = pair.map_err(tagged)?;
+                    rules.symbols.insert(symbol.to_string(), rules.find_move(name).map_err(tagged)?);
+                }
+            } else if let Some(outcomes) = line.strip_prefix("outcomes:") {
+                for pair in assignments(outcomes) {
+                    let (symbol, result) = pair.map_err(tagged)?;
+                    rules.outcomes.insert(symbol.to_string(), result.parse().map_err(tagged)?);
+                }
+            } else if let Some(scores) = line.strip_prefix("scores:") {
+                for pair in assignments(scores) {
+                    let (name, score) = pair.map_err(tagged)?;
+                    let score: u32 = score.parse::<u32>().map_err(|e| tagged(e.into()))?;
+                    match name.parse::<GameResult>() {
+                        Ok(result) => { rules.result_scores.insert(result, score); },
+                        Err(_) => {
+                            let m: Move = rules.find_move(name).map_err(tagged)?;
+                            rules.move_scores[m.0] = score;
+                        },
+                    }
+                }
+            } else if let Some((winner, losers)) = line.split_once(" beats ") {
+                if !custom_beats {
+                    rules.beats = vec![vec![false; n]; n];
+                    custom_beats = true;
+                }
+                let winner: Move = rules.find_move(winner.trim()).map_err(tagged)?;
+                for loser in losers.split_whitespace() {
+                    let loser: Move = rules.find_move(loser).map_err(tagged)?;
+                    rules.beats[winner.0][loser.0] = true;
+                }
+            } else {
+                return Err(tagged(PuzzleError::parse("Expected symbols:, outcomes:, scores: or a line of the form <move> beats <moves>")));
+            }
+        }
+        rules.validate()?;
+        if let Some(result) = GameResult::ALL.into_iter().find(|result| !rules.outcomes.values().any(|r| r == result)) {
+            return Err(PuzzleError::validation(format!("No symbol stands for the result {result:?}")));
+        }
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod test_rules {
+    use super::*;
+
+    const RPSLS: &str = "\
+# rock paper scissors lizard spock
+moves: Rock Paper Scissors Lizard Spock
+Rock beats Scissors Lizard
+Paper beats Rock Spock
+Scissors beats Paper Lizard
+Lizard beats Spock Paper
+Spock beats Scissors Rock
+symbols: A=Rock B=Paper C=Scissors D=Lizard E=Spock V=Rock W=Paper X=Scissors Y=Lizard Z=Spock
+outcomes: X=Loss Y=Draw Z=Win
+scores: Lizard=4 Spock=5";
+
+    #[test]
+    fn classic_moves_beat_the_move_before() {
+        let rules: Rules = Rules::classic();
+        assert!(rules.beats(Move::PAPER, Move::ROCK));
+        assert!(rules.beats(Move::SCISSORS, Move::PAPER));
+        assert!(rules.beats(Move::ROCK, Move::SCISSORS));
+        assert!(!rules.beats(Move::ROCK, Move::PAPER));
+    }
+
+    #[test]
+    fn rpsls_outcomes() {
+        let rules: Rules = RPSLS.parse().unwrap();
+        let (lizard, spock) = (rules.parse_move("D").unwrap(), rules.parse_move("E").unwrap());
+        assert_eq!(GameResult::outcome(&rules, spock, lizard), GameResult::Win);
+        assert_eq!(GameResult::outcome(&rules, lizard, Move::ROCK), GameResult::Win);
+        assert_eq!(GameResult::outcome(&rules, Move::ROCK, spock), GameResult::Win);
+        assert_eq!(rules.weaknesses(spock), vec![Move::PAPER, lizard]);
+        assert_eq!(GameResult::Win.move_from_outcome(&rules, spock), Move::PAPER);
+        assert_eq!(GameResult::Loss.move_from_outcome(&rules, spock), Move::ROCK);
+        assert_eq!(rules.score(spock, GameResult::Draw), 8);
+    }
+
+    #[test]
+    fn default_beats_for_five_moves_are_cyclic() {
+        let rules: Rules = "moves: A B C D E\nsymbols: a=A\noutcomes: l=Loss d=Draw w=Win".parse().unwrap();
+        assert_eq!(rules.strengths(Move(0)), vec![Move(3), Move(4)]);
+        assert_eq!(rules.weaknesses(Move(0)), vec![Move(1), Move(2)]);
+    }
+
+    #[test]
+    fn even_number_of_moves_is_rejected() {
+        let err: PuzzleError = "outcomes: X=Loss Y=Draw Z=Win\nmoves: Rock Paper".parse::<Rules>().unwrap_err();
+        assert_eq!(err.location().line, Some(2));
+    }
+
+    #[test]
+    fn unbalanced_beats_are_rejected() {
+        let rules: String = RPSLS.replace("Rock beats Scissors Lizard", "Rock beats Scissors Lizard Paper");
+        assert!(matches!(rules.parse::<Rules>(), Err(PuzzleError::Validation { .. })));
+    }
+
+    #[test]
+    fn unknown_move_names_the_line() {
+        let err: PuzzleError = RPSLS.replace("D=Lizard", "D=Lizzard").parse::<Rules>().unwrap_err();
+        assert_eq!(err.location().line, Some(8));
+        assert_eq!(err.details(), "Unknown move Lizzard");
+    }
+
+    #[test]
+    fn missing_outcome_symbol_is_rejected() {
+        assert!("moves: Rock Paper Scissors\noutcomes: X=Loss Y=Draw".parse::<Rules>().is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::IndexedRandom, SeedableRng};
+
+use super::rules::{GameResult, Move, Rules};
+
+/// A rock-paper-scissors bot. `history` holds every round played so far in the match, as (our move, their move).
+pub trait Strategy {
+    fn name(&self, rules: &Rules) -> String;
+
+    fn play(&mut self, rules: &Rules, history: &[(Move, Move)]) -> Move;
+
+    /// Called before every match, so that nothing carries over from the previous opponent.
+    fn reset(&mut self) {}
+}
+
+/// Always plays the same move.
+pub struct Fixed(pub Move);
+
+impl Strategy for Fixed {
+    fn name(&self, rules: &Rules) -> String {
+        format!("always {}", rules.name(self.0))
+    }
+
+    fn play(&mut self, _: &Rules, _: &[(Move, Move)]) -> Move {
+        self.0
+    }
+}
+
+/// Plays the given moves in order, starting over when they run out.
+pub struct Sequence {
+    pub name: String,
+    pub moves: Vec<Move>,
+}
+
+impl Strategy for Sequence {
+    fn name(&self, _: &Rules) -> String {
+        self.name.clone()
+    }
+
+    fn play(&mut self, _: &Rules, history: &[(Move, Move)]) -> Move {
+        self.moves[history.len() % self.moves.len()]
+    }
+}
+
+/// Plays uniformly random moves, from the same seed in every match.
+pub struct Random {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Random { seed, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+impl Strategy for Random {
+    fn name(&self, _: &Rules) -> String {
+        format!("random (seed {})", self.seed)
+    }
+
+    fn play(&mut self, rules: &Rules, _: &[(Move, Move)]) -> Move {
+        *rules.moves().collect::<Vec<Move>>().choose(&mut self.rng).unwrap()
+    }
+
+    fn reset(&mut self) {
+        self.rng = StdRng::seed_from_u64(self.seed);
+    }
+}
+
+/// Beats the move the opponent has played most often so far.
+pub struct FrequencyCounter;
+
+impl Strategy for FrequencyCounter {
+    fn name(&self, _: &Rules) -> String {
+        "frequency counter".to_string()
+    }
+
+    fn play(&mut self, rules: &Rules, history: &[(Move, Move)]) -> Move {
+        let counts = history.iter().map(|(_, theirs)| *theirs).counts();
+        // ties go to the move listed first in the rules
+        match rules.moves().rev().max_by_key(|m| counts.get(m).copied().unwrap_or(0)) {
+            Some(favourite) if !history.is_empty() => rules.weaknesses(favourite)[0],
+            _ => rules.moves().next().unwrap(),
+        }
+    }
+}
+
+/// Looks for the last time the opponent played their latest `length` moves and beats whatever they played next.
+pub struct PatternMatcher {
+    pub length: usize,
+}
+
+impl Strategy for PatternMatcher {
+    fn name(&self, _: &Rules) -> String {
+        format!("pattern matcher ({})", self.length)
+    }
+
+    fn play(&mut self, rules: &Rules, history: &[(Move, Move)]) -> Move {
+        let theirs: Vec<Move> = history.iter().map(|(_, theirs)| *theirs).collect();
+        let predicted: Option<Move> = theirs.len().checked_sub(self.length).and_then(|start| {
+            let pattern: &[Move] = &theirs[start..];
+            (0..start).rev().find(|i| theirs[*i..*i + self.length] == *pattern).map(|i| theirs[i + self.length])
+        });
+        match predicted {
+            Some(predicted) => rules.weaknesses(predicted)[0],
+            None => rules.moves().next().unwrap(),
+        }
+    }
+}
+
+/// How a bot fared over a round robin. Matches are worth 3 points for a win and 1 for a draw.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Standing {
+    pub name: String,
+    pub won: u32,
+    pub drawn: u32,
+    pub lost: u32,
+    /// total of the round scores over every match
+    pub score: u32,
+}
+
+impl Standing {
+    pub fn points(&self) -> u32 {
+        3 * self.won + self.drawn
+    }
+}
+
+/// Plays `rounds` rounds between two bots, returning the total score of each.
+pub fn play_match(first: &mut dyn Strategy, second: &mut dyn Strategy, rules: &Rules, rounds: usize) -> (u32, u32) {
+    first.reset();
+    second.reset();
+    let mut history: Vec<(Move, Move)> = Vec::with_capacity(rounds);
+    let mut mirrored: Vec<(Move, Move)> = Vec::with_capacity(rounds);
+    let (mut first_score, mut second_score) = (0, 0);
+    for _ in 0..rounds {
+        let (a, b) = (first.play(rules, &history), second.play(rules, &mirrored));
+        first_score += rules.score(a, GameResult::outcome(rules, b, a));
+        second_score += rules.score(b, GameResult::outcome(rules, a, b));
+        history.push((a, b));
+        mirrored.push((b, a));
+    }
+    (first_score, second_score)
+}
+
+/// Every bot plays every other bot once. The standings are ordered by points, then by total score.
+pub fn round_robin(bots: &mut [Box<dyn Strategy>], rules: &Rules, rounds: usize) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = bots.iter().map(|bot| Standing { name: bot.name(rules), won: 0, drawn: 0, lost: 0, score: 0 }).collect();
+    for (i, j) in (0..bots.len()).tuple_combinations() {
+        let (left, right) = bots.split_at_mut(j);
+        let (first, second) = play_match(left[i].as_mut(), right[0].as_mut(), rules, rounds);
+        standings[i].score += first;
+        standings[j].score += second;
+        match first.cmp(&second) {
+            std::cmp::Ordering::Greater => { standings[i].won += 1; standings[j].lost += 1; },
+            std::cmp::Ordering::Less => { standings[i].lost += 1; standings[j].won += 1; },
+            std::cmp::Ordering::Equal => { standings[i].drawn += 1; standings[j].drawn += 1; },
+        }
+    }
+    standings.sort_by_key(|standing| std::cmp::Reverse((standing.points(), standing.score)));
+    standings
+}
+
+pub struct League(pub Vec<Standing>);
+
+impl Display for League {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width: usize = self.0.iter().map(|standing| standing.name.len()).max().unwrap_or(0);
+        write!(f, "{:<width$}  {:>3} {:>3} {:>3} {:>6} {:>8}", "bot", "W", "D", "L", "points", "score")?;
+        for standing in self.0.iter() {
+            write!(f, "\n{:<width$}  {:>3} {:>3} {:>3} {:>6} {:>8}", standing.name, standing.won, standing.drawn, standing.lost, standing.points(), standing.score)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_tournament {
+    use super::*;
+
+    #[test]
+    fn frequency_counter_beats_a_fixed_bot() {
+        let rules: Rules = Rules::classic();
+        let (counter, rock) = play_match(&mut FrequencyCounter, &mut Fixed(Move::ROCK), &rules, 10);
+        // one opening draw, then paper every round
+        assert_eq!((counter, rock), ((1 + 3) + 9 * (2 + 6), 1 + 3 + 9));
+    }
+
+    #[test]
+    fn pattern_matcher_learns_a_cycle() {
+        let rules: Rules = Rules::classic();
+        let mut cycle: Sequence = Sequence { name: "cycle".to_string(), moves: vec![Move::ROCK, Move::PAPER, Move::SCISSORS] };
+        let mut matcher: PatternMatcher = PatternMatcher { length: 2 };
+        let (matcher_score, cycle_score) = play_match(&mut matcher, &mut cycle, &rules, 10);
+        assert!(matcher_score > cycle_score);
+        let history: Vec<(Move, Move)> = vec![(Move::ROCK, Move::ROCK), (Move::ROCK, Move::PAPER), (Move::ROCK, Move::SCISSORS), (Move::ROCK, Move::ROCK)];
+        // paper and scissors were followed by rock last time
+        assert_eq!(matcher.play(&rules, &[history.clone(), vec![(Move::ROCK, Move::PAPER), (Move::ROCK, Move::SCISSORS)]].concat()), Move::PAPER);
+        assert_eq!(matcher.play(&rules, &history[..2]), Move::ROCK);
+    }
+
+    #[test]
+    fn random_bot_replays_after_reset() {
+        let rules: Rules = Rules::classic();
+        let mut bot: Random = Random::new(3);
+        let first: Vec<Move> = (0..20).map(|_| bot.play(&rules, &[])).collect();
+        bot.reset();
+        assert_eq!((0..20).map(|_| bot.play(&rules, &[])).collect::<Vec<Move>>(), first);
+    }
+
+    #[test]
+    fn round_robin_plays_every_pair_once() {
+        let rules: Rules = Rules::classic();
+        let mut bots: Vec<Box<dyn Strategy>> = vec![Box::new(Fixed(Move::ROCK)), Box::new(Fixed(Move::PAPER)), Box::new(FrequencyCounter)];
+        let standings: Vec<Standing> = round_robin(&mut bots, &rules, 100);
+        assert!(standings.iter().all(|standing| standing.won + standing.drawn + standing.lost == 2));
+        assert_eq!(standings[0].name, "frequency counter");
+        assert_eq!(standings.iter().map(|standing| standing.points()).collect::<Vec<u32>>(), vec![6, 3, 0]);
+    }
+
+    #[test]
+    fn league_table_lines_up() {
+        let league: League = League(vec![Standing { name: "frequency counter".to_string(), won: 2, drawn: 0, lost: 0, score: 1300 }]);
+        assert_eq!(league.to_string(), [
+            "bot                  W   D   L points    score",
+            "frequency counter    2   0   0      6     1300",
+        ].join("\n"));
+    }
+}
@@ -1 +1,2 @@
+pub mod repacking;
 pub mod rucksack;
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use super::rucksack::{get_elfgroup_badge, item_priority, sum_item_priorities, Compartment, ElfGroup, Item, ItemSet, Rucksack};
+
+/// Trading `left` out of the first compartment for `right` out of the second.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Swap {
+    pub left: Item,
+    pub right: Item,
+}
+
+/// Trading an item between two rucksacks of a group. Both ends are (rucksack, compartment, item), with the compartments numbered 0 and 1.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Exchange {
+    pub from: (usize, usize, Item),
+    pub to: (usize, usize, Item),
+}
+
+/// The swaps that keep every item type in one compartment, and the priority of the items found in both compartments before and after.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Repacking {
+    pub swaps: Vec<Swap>,
+    pub priority: (u32, u32),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GroupRepacking {
+    pub exchanges: Vec<Exchange>,
+    /// `None` for the rucksacks that still cannot be repacked after the exchanges
+    pub repackings: Vec<Option<Repacking>>,
+    /// the priority of the group badge before and after the exchanges, if the group has exactly one badge
+    pub badge_priority: (Option<u32>, Option<u32>),
+}
+
+fn misplaced_priority(rucksack: &Rucksack) -> u32 {
+    sum_item_priorities(ItemSet::new(&rucksack.0).intersection(ItemSet::new(&rucksack.1)).items())
+}
+
+fn compartment_mut(rucksack: &mut Rucksack, compartment: usize) -> &mut Compartment {
+    match compartment {
+        0 => &mut rucksack.0,
+        _ => &mut rucksack.1,
+    }
+}
+
+fn replace_one(compartment: &mut Compartment, item: Item, replacement: Item) {
+    if let Some(position) = compartment.iter().position(|i| *i == item) {
+        compartment[position] = replacement;
+    }
+}
+
+pub fn apply_swaps(rucksack: &Rucksack, swaps: &[Swap]) -> Rucksack {
+    let mut rucksack: Rucksack = rucksack.clone();
+    for swap in swaps {
+        replace_one(&mut rucksack.0, swap.left, swap.right);
+        replace_one(&mut rucksack.1, swap.right, swap.left);
+    }
+    rucksack
+}
+
+fn apply_exchange(group: &mut ElfGroup, exchange: Exchange) {
+    let ((a, a_compartment, a_item), (b, b_compartment, b_item)) = (exchange.from, exchange.to);
+    replace_one(compartment_mut(&mut group[a], a_compartment), a_item, b_item);
+    replace_one(compartment_mut(&mut group[b], b_compartment), b_item, a_item);
+}
+
+/// The fewest swaps that leave no item type in both compartments, or `None` when that cannot be done.
+///
+/// Every swap moves one item each way, so the compartments stay the same size. Choosing which item
+/// types end up in the first compartment is a subset sum over the type counts: the chosen types must
+/// fill the compartment exactly, and each of their items in the second compartment costs one swap.
+pub fn plan_swaps(rucksack: &Rucksack) -> Option<Vec<Swap>> {
+    let mut counts: BTreeMap<Item, (usize, usize)> = BTreeMap::new();
+    rucksack.0.iter().for_each(|item| counts.entry(*item).or_default().0 += 1);
+    rucksack.1.iter().for_each(|item| counts.entry(*item).or_default().1 += 1);
+    let size: usize = rucksack.0.len();
+    // cheapest (swaps, types kept in the first compartment) whose items add up to each size
+    let mut best: Vec<Option<(usize, Vec<Item>)>> = vec![None; size + 1];
+    best[0] = Some((0, Vec::new()));
+    for (item, (left, right)) in counts {
+        for total in (left + right..=size).rev() {
+            if let Some((swaps, kept)) = best[total - left - right].clone() {
+                if best[total].as_ref().is_none_or(|(cheapest, _)| swaps + right < *cheapest) {
+                    best[total] = Some((swaps + right, [kept, vec![item]].concat()));
+                }
+            }
+        }
+    }
+    let (_, kept) = best[size].clone()?;
+    let to_right = rucksack.0.iter().filter(|item| !kept.contains(item));
+    let to_left = rucksack.1.iter().filter(|item| kept.contains(item));
+    Some(to_right.zip(to_left).map(|(left, right)| Swap { left: *left, right: *right }).collect())
+}
+
+pub fn repack(rucksack: &Rucksack) -> Option<Repacking> {
+    let swaps: Vec<Swap> = plan_swaps(rucksack)?;
+    let priority: (u32, u32) = (misplaced_priority(rucksack), misplaced_priority(&apply_swaps(rucksack, &swaps)));
+    Some(Repacking { swaps, priority })
+}
+
+/// Repacks every rucksack of the group. A rucksack that cannot be repacked on its own first trades
+/// one item with another rucksack of the group, picking the trade that needs the fewest swaps in
+/// both rucksacks afterwards.
+pub fn repack_group(group: &ElfGroup) -> GroupRepacking {
+    let badge_priority = |group: &ElfGroup| get_elfgroup_badge(group).ok().map(|badge| item_priority(&badge) as u32);
+    let mut repacked: ElfGroup = group.clone();
+    let mut exchanges: Vec<Exchange> = Vec::new();
+    for a in 0..repacked.len() {
+        if plan_swaps(&repacked[a]).is_some() {
+            continue;
+        }
+        let ends = |rucksack: usize| {
+            let (left, right) = &repacked[rucksack];
+            let left = left.iter().unique().map(move |item| (rucksack, 0, *item));
+            left.chain(right.iter().unique().map(move |item| (rucksack, 1, *item)))
+        };
+        let cheapest: Option<(usize, Exchange)> = (0..repacked.len()).filter(|b| *b != a)
+            .flat_map(|b| ends(a).cartesian_product(ends(b).collect::<Vec<_>>()))
+            .filter(|(from, to)| from.2 != to.2)
+            .filter_map(|(from, to)| {
+                let exchange: Exchange = Exchange { from, to };
+                let mut trial: ElfGroup = repacked.clone();
+                apply_exchange(&mut trial, exchange);
+                let swaps: usize = plan_swaps(&trial[from.0])?.len() + plan_swaps(&trial[to.0])?.len();
+                Some((swaps, exchange))
+            })
+            .min_by_key(|(swaps, _)| *swaps);
+        if let Some((_, exchange)) = cheapest {
+            apply_exchange(&mut repacked, exchange);
+            exchanges.push(exchange);
+        }
+    }
+    GroupRepacking {
+        exchanges,
+        // the priorities before count from the rucksacks as they were packed, before any exchange
+        repackings: repacked.iter().zip(group).map(|(rucksack, original)| repack(rucksack).map(|repacking| Repacking {
+            priority: (misplaced_priority(original), repacking.priority.1),
+            ..repacking
+        })).collect(),
+        badge_priority: (badge_priority(group), badge_priority(&repacked)),
+    }
+}
+
+#[cfg(test)]
+mod test_repacking {
+    use super::*;
+
+    fn rucksack(s: &str) -> Rucksack {
+        let (left, right) = s.split_at(s.len() / 2);
+        (left.chars().collect(), right.chars().collect())
+    }
+
+    #[test]
+    fn one_swap_moves_the_shared_item() {
+        let repacking: Repacking = repack(&rucksack("vJrwpWtwJgWrhcsFMMfFFhFp")).unwrap();
+        assert_eq!(repacking.swaps.len(), 1);
+        assert_eq!(repacking.priority, (16, 0));
+    }
+
+    #[test]
+    fn swaps_are_minimal() {
+        // keeping a and b on the left takes one swap, keeping c and d there would take three
+        let rucksack: Rucksack = rucksack("aabdbccc");
+        let swaps: Vec<Swap> = plan_swaps(&rucksack).unwrap();
+        assert_eq!(swaps, vec![Swap { left: 'd', right: 'b' }]);
+        let (left, right) = apply_swaps(&rucksack, &swaps);
+        assert_eq!((left.iter().collect::<String>(), right.iter().collect::<String>()), ("aabb".to_string(), "dccc".to_string()));
+    }
+
+    #[test]
+    fn packed_rucksack_needs_no_swaps() {
+        assert_eq!(repack(&rucksack("abcdef")), Some(Repacking { swaps: vec![], priority: (0, 0) }));
+    }
+
+    #[test]
+    fn unbalanced_types_cannot_be_repacked() {
+        // every type has two items, so no choice of types fills a compartment of three
+        assert_eq!(plan_swaps(&rucksack("abcabc")), None);
+    }
+
+    #[test]
+    fn group_exchange_makes_a_rucksack_repackable() {
+        let group: ElfGroup = vec![rucksack("abcabc"), rucksack("xyzwzy")];
+        let repacking: GroupRepacking = repack_group(&group);
+        assert_eq!(repacking.exchanges.len(), 1);
+        assert!(repacking.repackings.iter().all(|repacking| repacking.as_ref().is_some_and(|r| r.priority.1 == 0)));
+    }
+}
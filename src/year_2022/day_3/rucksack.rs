@@ -1,13 +1,15 @@
-use std::collections::HashSet;
+use rand::{rngs::StdRng, seq::{IndexedRandom, SliceRandom}, RngExt};
 
-use itertools::Itertools;
+use crate::{cli::Args, error::{parse_lines, PuzzleError}};
 
-type Item = char;
-type Compartment = Vec<Item>;
-type Rucksack = (Compartment, Compartment);
-type ElfGroup = (Rucksack, Rucksack, Rucksack);
+use super::repacking::{repack, repack_group, Exchange, GroupRepacking, Repacking};
 
-fn item_priority(item: &Item) -> u8 {
+pub(super) type Item = char;
+pub(super) type Compartment = Vec<Item>;
+pub(super) type Rucksack = (Compartment, Compartment);
+pub(super) type ElfGroup = Vec<Rucksack>;
+
+pub(super) fn item_priority(item: &Item) -> u8 {
     let item = *item;
     if item.is_lowercase() {
         return (item as u8) - 96;
@@ -17,7 +19,7 @@ fn item_priority(item: &Item) -> u8 {
     0
 }
 
-fn sum_item_priorities(items: Vec<Item>) -> u32 {
+pub(super) fn sum_item_priorities(items: Vec<Item>) -> u32 {
     items
         .iter()
         .map(|item: &Item| item_priority(item) as u32)
@@ -28,64 +30,258 @@ fn new_compartment(items: String) -> Compartment {
     items.chars().collect()
 }
 
-fn common_items(compartment_a: &Compartment, compartment_b: &Compartment) -> Compartment {
-    let compartment_a: HashSet<char> = HashSet::from_iter(compartment_a.into_iter().map(|i| *i));
-    let compartment_b: HashSet<char> = HashSet::from_iter(compartment_b.into_iter().map(|i| *i));
-    compartment_a
-        .intersection(&compartment_b)
-        .map(|i| *i)
-        .collect()
+/// The item types of a compartment as a bitmask, where bit `priority - 1` stands for each item type.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) struct ItemSet(u64);
+
+impl ItemSet {
+    const ALL: ItemSet = ItemSet((1 << 52) - 1);
+
+    pub(super) fn new(items: &[Item]) -> Self {
+        ItemSet(items.iter().fold(0, |mask, item| mask | 1 << (item_priority(item) - 1)))
+    }
+
+    fn union(self, other: ItemSet) -> Self {
+        ItemSet(self.0 | other.0)
+    }
+
+    pub(super) fn intersection(self, other: ItemSet) -> Self {
+        ItemSet(self.0 & other.0)
+    }
+
+    pub(super) fn items(self) -> Vec<Item> {
+        (0..52).filter(|bit| self.0 & 1 << bit != 0)
+            .map(|bit: u8| if bit < 26 { (b'a' + bit) as char } else { (b'A' + bit - 26) as char })
+            .collect()
+    }
+
+    /// The one item in the set, where `holders` says whose items were intersected for the error.
+    fn single_item(self, holders: &str) -> Result<Item, PuzzleError> {
+        match self.items()[..] {
+            [item] => Ok(item),
+            [] => Err(PuzzleError::validation(format!("{holders} have no item in common"))),
+            ref items => Err(PuzzleError::validation(format!("{holders} have {} items in common: {}", items.len(), items.iter().collect::<String>()))),
+        }
+    }
 }
 
-fn common_item(compartment_a: &Compartment, compartment_b: &Compartment) -> Item {
-    *common_items(compartment_a, compartment_b).get(0).unwrap()
+fn common_item(compartment_a: &Compartment, compartment_b: &Compartment) -> Result<Item, PuzzleError> {
+    ItemSet::new(compartment_a).intersection(ItemSet::new(compartment_b)).single_item("The compartments")
 }
 
-fn merge_rucksack(rucksack: Rucksack) -> Compartment {
-    rucksack.0.into_iter().chain(rucksack.1).collect()
+fn rucksack_items(rucksack: &Rucksack) -> ItemSet {
+    ItemSet::new(&rucksack.0).union(ItemSet::new(&rucksack.1))
 }
 
-fn get_elfgroup_badge(group: ElfGroup) -> Item {
-    let (group_a, group_b, group_c) = group;
-    let init_set = common_items(&merge_rucksack(group_a), &merge_rucksack(group_b));
-    common_item(&init_set, &merge_rucksack(group_c))
+pub(super) fn get_elfgroup_badge(group: &ElfGroup) -> Result<Item, PuzzleError> {
+    group.iter().map(rucksack_items).fold(ItemSet::ALL, ItemSet::intersection).single_item("The rucksacks of the group")
 }
 
-fn collect_elf_group(rucksacks: Vec<Rucksack>) -> Vec<ElfGroup> {
-    let mut group = Vec::new();
-    for (_, enum_grp) in &rucksacks.into_iter().enumerate().chunk_by(|(i, _)| i / 3) {
-        let enum_grp: ElfGroup = enum_grp.map(|(_, e)| e).collect_tuple().unwrap();
-        group.push(enum_grp);
+/// Splits the rucksacks, numbered by the line they were on, into groups of `group_size`. Badge errors point at the first line of the group.
+pub(super) fn collect_elf_groups(rucksacks: &[(usize, Rucksack)], group_size: usize) -> Result<Vec<(usize, ElfGroup)>, PuzzleError> {
+    if group_size == 0 || !rucksacks.len().is_multiple_of(group_size) {
+        return Err(PuzzleError::validation(format!("Cannot split {} rucksacks into groups of {group_size}", rucksacks.len())));
     }
-    group
+    Ok(rucksacks.chunks(group_size).map(|group| (group[0].0, group.iter().map(|(_, rucksack)| rucksack.clone()).collect())).collect())
 }
 
-fn parse_rucksack(line: String) -> Rucksack {
+fn parse_rucksack(line: &str) -> Result<Rucksack, PuzzleError> {
+    if let Some(column) = line.chars().position(|item| !item.is_ascii_alphabetic()) {
+        return Err(PuzzleError::parse(format!("Items must be letters, found {:?}", line[column..].chars().next().unwrap())).at_column(column + 1));
+    }
+    if !line.len().is_multiple_of(2) {
+        return Err(PuzzleError::parse(format!("Cannot split {} items evenly into two compartments", line.len())));
+    }
     let mid = line.len() / 2;
     let (comp_1, comp_2) = line.split_at(mid);
 
-    (
+    Ok((
         new_compartment(comp_1.to_owned()),
         new_compartment(comp_2.to_owned()),
-    )
+    ))
 }
 
-pub fn solve(lines: Vec<String>) {
-    let rucksacks: Vec<Rucksack> = lines
-        .iter()
-        .map(|line: &String| parse_rucksack(line.clone()))
-        .collect();
+/// Every non-empty line as a rucksack, with the line number it was on.
+pub(super) fn parse_rucksacks(lines: &[String]) -> Result<Vec<(usize, Rucksack)>, PuzzleError> {
+    let rucksacks: Vec<Option<Rucksack>> = parse_lines(lines, |line| match line.is_empty() {
+        true => Ok(None),
+        false => parse_rucksack(line).map(Some),
+    })?;
+    Ok(rucksacks.into_iter().enumerate().filter_map(|(i, rucksack)| rucksack.map(|rucksack| (i + 1, rucksack))).collect())
+}
+
+/// Generates `size` elf groups. Every rucksack has exactly one item in both compartments and
+/// every group shares exactly one badge, as each elf packs from its own third of the alphabet.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for _ in 0..size {
+        let mut items: Vec<Item> = ('a'..='z').chain('A'..='Z').collect();
+        items.shuffle(rng);
+        let badge: Item = items[0];
+        for pool in items[1..].chunks(17) {
+            let (misplaced, (left_pool, right_pool)) = (pool[0], pool[1..].split_at(8));
+            let compartment_size: usize = rng.random_range(2..=16);
+            let mut left: Compartment = (0..compartment_size).map(|_| *left_pool.choose(rng).unwrap()).collect();
+            let mut right: Compartment = (0..compartment_size).map(|_| *right_pool.choose(rng).unwrap()).collect();
+            left[0] = misplaced;
+            right[0] = misplaced;
+            if rng.random_bool(0.5) { left[1] = badge } else { right[1] = badge }
+            left.shuffle(rng);
+            right.shuffle(rng);
+            lines.push(left.into_iter().chain(right).collect());
+        }
+    }
+    lines
+}
+
+fn solve_with_group_size(lines: Vec<String>, group_size: usize) -> Result<(), PuzzleError> {
+    let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(&lines)?;
     let common_items: Vec<Item> = rucksacks
         .iter()
-        .map(|rucksack: &Rucksack| common_item(&rucksack.0, &rucksack.1))
-        .collect();
+        .map(|(line, rucksack)| common_item(&rucksack.0, &rucksack.1).map_err(|e| e.at_line(*line).with_text(&lines[line - 1])))
+        .collect::<Result<Vec<Item>, PuzzleError>>()?;
     let total_priority: u32 = sum_item_priorities(common_items);
     println!("Total priority sum is {}", total_priority);
 
-    let elfgroup_badges: Vec<Item> = collect_elf_group(rucksacks)
-        .into_iter()
-        .map(|group| get_elfgroup_badge(group))
-        .collect();
+    let elfgroup_badges: Vec<Item> = collect_elf_groups(&rucksacks, group_size)?
+        .iter()
+        .map(|(line, group)| get_elfgroup_badge(group).map_err(|e| e.at_line(*line)))
+        .collect::<Result<Vec<Item>, PuzzleError>>()?;
     let total_badge_priorities: u32 = sum_item_priorities(elfgroup_badges);
     println!("Elf Groups: {}", total_badge_priorities);
+    Ok(())
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    solve_with_group_size(lines, 3)
+}
+
+fn describe_repacking(repacking: &Option<Repacking>) -> String {
+    match repacking {
+        Some(Repacking { swaps, priority: (before, after) }) => {
+            let swaps: Vec<String> = swaps.iter().map(|swap| format!("{} for {}", swap.left, swap.right)).collect();
+            format!("{} swaps [{}], priority {before} -> {after}", swaps.len(), swaps.join(", "))
+        },
+        None => "cannot be repacked".to_string(),
+    }
+}
+
+/// Plans the swaps that keep every item type in one compartment, letting the rucksacks of a group
+/// trade items first with `--between-rucksacks`.
+fn print_repacking(lines: &[String], group_size: usize, between_rucksacks: bool) -> Result<(), PuzzleError> {
+    let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(lines)?;
+    let repackings: Vec<Option<Repacking>> = match between_rucksacks {
+        false => rucksacks.iter().map(|(_, rucksack)| repack(rucksack)).collect(),
+        true => {
+            let mut repackings: Vec<Option<Repacking>> = Vec::new();
+            for (line, group) in collect_elf_groups(&rucksacks, group_size)? {
+                let group: GroupRepacking = repack_group(&group);
+                let exchanges: Vec<String> = group.exchanges.iter()
+                    .map(|Exchange { from: (a, _, a_item), to: (b, _, b_item) }| format!("{a_item} from elf {} for {b_item} from elf {}", a + 1, b + 1))
+                    .collect();
+                let badge = |priority: Option<u32>| priority.map_or("none".to_string(), |priority| priority.to_string());
+                println!("Group at line {line}: {} exchanges [{}], badge priority {} -> {}", exchanges.len(), exchanges.join(", "), badge(group.badge_priority.0), badge(group.badge_priority.1));
+                repackings.extend(group.repackings);
+            }
+            repackings
+        },
+    };
+    for ((line, _), repacking) in rucksacks.iter().zip(repackings.iter()) {
+        println!("Line {line}: {}", describe_repacking(repacking));
+    }
+    let planned: Vec<&Repacking> = repackings.iter().flatten().collect();
+    println!(
+        "{} swaps in total, priority {} -> {}, {} rucksacks cannot be repacked",
+        planned.iter().map(|repacking| repacking.swaps.len()).sum::<usize>(),
+        planned.iter().map(|repacking| repacking.priority.0).sum::<u32>(),
+        planned.iter().map(|repacking| repacking.priority.1).sum::<u32>(),
+        repackings.len() - planned.len(),
+    );
+    Ok(())
+}
+
+/// Finds the badges of groups of `--group-size` elves rather than three, or plans repacking with `--repack`.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    let group_size: usize = args.get("group-size")?.unwrap_or(3);
+    match args.has("repack") {
+        true => print_repacking(&lines, group_size, args.has("between-rucksacks")),
+        false => solve_with_group_size(lines, group_size),
+    }
+}
+
+#[cfg(test)]
+mod test_rucksack {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn lines(s: &[&str]) -> Vec<String> {
+        s.iter().map(|line| line.to_string()).collect()
+    }
+
+    const EXAMPLE: [&str; 6] = [
+        "vJrwpWtwJgWrhcsFMMfFFhFp",
+        "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+        "PmmdzqPrVvPwwTWBwg",
+        "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+        "ttgJtRGJQctTZtZT",
+        "CrZsJsPPZsGzwwsLwLmpwMDw",
+    ];
+
+    #[test]
+    fn item_set_intersection() {
+        let set: ItemSet = ItemSet::new(&new_compartment("vJrwpWtwJgWr".to_string())).intersection(ItemSet::new(&new_compartment("hcsFMMfFFhFp".to_string())));
+        assert_eq!(set.items(), vec!['p']);
+        assert_eq!(ItemSet::new(&['a', 'Z']), ItemSet(1 | 1 << 51));
+    }
+
+    #[test]
+    fn example_items_and_badges() {
+        let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(&lines(&EXAMPLE)).unwrap();
+        let items: Vec<Item> = rucksacks.iter().map(|(_, (a, b))| common_item(a, b).unwrap()).collect();
+        assert_eq!(items, vec!['p', 'L', 'P', 'v', 't', 's']);
+        let badges: Vec<Item> = collect_elf_groups(&rucksacks, 3).unwrap().iter().map(|(_, group)| get_elfgroup_badge(group).unwrap()).collect();
+        assert_eq!(badges, vec!['r', 'Z']);
+    }
+
+    #[test]
+    fn groups_of_two() {
+        let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(&lines(&["abcA", "dBcd"])).unwrap();
+        let groups: Vec<(usize, ElfGroup)> = collect_elf_groups(&rucksacks, 2).unwrap();
+        assert_eq!(get_elfgroup_badge(&groups[0].1), Ok('c'));
+    }
+
+    #[test]
+    fn uneven_groups_are_rejected() {
+        let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(&lines(&EXAMPLE[..4])).unwrap();
+        assert!(collect_elf_groups(&rucksacks, 3).is_err());
+    }
+
+    #[test]
+    fn odd_length_line_is_rejected() {
+        let err: PuzzleError = parse_rucksacks(&lines(&["abab", "", "abcab"])).unwrap_err();
+        assert_eq!(err.location().line, Some(3));
+    }
+
+    #[test]
+    fn non_letter_item_is_rejected() {
+        assert_eq!(parse_rucksack("ab1b").unwrap_err().location().column, Some(3));
+    }
+
+    #[test]
+    fn zero_and_multiple_common_items_are_errors() {
+        assert_eq!(common_item(&vec!['a', 'b'], &vec!['c', 'd']).unwrap_err().details(), "The compartments have no item in common");
+        assert_eq!(common_item(&vec!['a', 'b'], &vec!['b', 'a']).unwrap_err().details(), "The compartments have 2 items in common: ab");
+    }
+
+    #[test]
+    fn generated_rucksacks_share_one_item() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(3), 10);
+        let rucksacks: Vec<(usize, Rucksack)> = parse_rucksacks(&lines).unwrap();
+        assert_eq!(rucksacks.len(), 30);
+        assert!(rucksacks.iter().all(|(_, (left, right))| common_item(left, right).is_ok()));
+        for (_, group) in collect_elf_groups(&rucksacks, 3).unwrap() {
+            assert!(get_elfgroup_badge(&group).is_ok());
+        }
+    }
 }
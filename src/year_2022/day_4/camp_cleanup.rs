@@ -1,6 +1,11 @@
 use itertools::Itertools;
+use rand::{rngs::StdRng, RngExt};
 
-type ElfPair = (u32, u32);
+use crate::{cli::Args, error::{parse_lines, PuzzleError}};
+
+use super::coverage::{conflicting_pairs, Assignment, Coverage};
+
+pub(super) type ElfPair = (u32, u32);
 
 fn has_superset(pair_a: ElfPair, pair_b: ElfPair) -> bool {
     let a_superset = pair_a.1 >= pair_b.1 && pair_a.0 <= pair_b.0;
@@ -12,26 +17,46 @@ fn has_overlap(pair_a: ElfPair, pair_b: ElfPair) -> bool {
     !(pair_a.0 > pair_b.1 || pair_a.1 < pair_b.0)
 }
 
-fn parse_elf_set(range_str: String) -> ElfPair {
-    range_str
+fn parse_elf_set(range_str: String) -> Result<ElfPair, PuzzleError> {
+    let (start, end): ElfPair = range_str
         .split("-")
-        .map(|rm| rm.parse::<u32>().unwrap())
+        .map(|rm| rm.parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>()?
+        .into_iter()
         .collect_tuple()
-        .unwrap()
+        .ok_or(PuzzleError::parse(format!("{range_str} is not a range of the form start-end")))?;
+    match start <= end {
+        true => Ok((start, end)),
+        false => Err(PuzzleError::validation(format!("{range_str} ends before it starts"))),
+    }
 }
 
-fn parse_elf_pair(line: String) -> (ElfPair, ElfPair) {
+fn parse_elf_pair(line: String) -> Result<(ElfPair, ElfPair), PuzzleError> {
     line.split(",")
         .map(|elf| parse_elf_set(elf.to_owned()))
+        .collect::<Result<Vec<ElfPair>, _>>()?
+        .into_iter()
         .collect_tuple()
-        .unwrap()
+        .ok_or(PuzzleError::parse("Line does not contain exactly two comma separated ranges"))
+}
+
+fn generate_elf_set(rng: &mut StdRng) -> ElfPair {
+    let (start, end): (u32, u32) = (rng.random_range(1..=99), rng.random_range(1..=99));
+    (start.min(end), start.max(end))
 }
 
-pub fn solve(lines: Vec<String>) {
+/// Generates `size` pairs of section assignments within sections 1-99.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    (0..size).map(|_| {
+        let ((a, b), (c, d)) = (generate_elf_set(rng), generate_elf_set(rng));
+        format!("{a}-{b},{c}-{d}")
+    }).collect()
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
     let mut superset_cntr = 0;
     let mut overlap_cntr = 0;
-    for line in lines {
-        let elf_pair = parse_elf_pair(line);
+    for elf_pair in parse_lines(&lines, |line| parse_elf_pair(line.to_owned()))? {
         if has_superset(elf_pair.0, elf_pair.1) {
             overlap_cntr += 1;
             superset_cntr += 1;
@@ -41,4 +66,53 @@ pub fn solve(lines: Vec<String>) {
     }
     println!("Total Supersets: {superset_cntr}");
     println!("Total overlaps: {overlap_cntr}");
+    Ok(())
+}
+
+/// Sweeps over the assignments of every elf with `--coverage`, counting the sections covered by at
+/// least `--depth` elves (2 by default) and listing the overlapping pairs across lines with `--conflicts`.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    if !args.has("coverage") {
+        return solve(lines);
+    }
+    let assignments: Vec<Assignment> = parse_lines(&lines, |line| parse_elf_pair(line.to_owned()))?.into_iter().enumerate()
+        .flat_map(|(i, (a, b))| [Assignment { line: i + 1, sections: a }, Assignment { line: i + 1, sections: b }])
+        .collect();
+    let coverage: Coverage = Coverage::new(&assignments);
+    let depth: u32 = args.get("depth")?.unwrap_or(2);
+    println!("Sections covered: {}", coverage.total());
+    match coverage.uncovered()[..] {
+        [] => println!("Uncovered sections: none"),
+        ref uncovered => println!("Uncovered sections: {}", uncovered.iter().map(|(first, last)| format!("{first}-{last}")).join(", ")),
+    }
+    println!("Sections covered by at least {depth} elves: {}", coverage.covered_by_at_least(depth));
+    println!("Maximum overlap depth: {}", coverage.max_depth());
+    println!("Overlapping pairs across lines: {}", coverage.conflicts());
+    if args.has("conflicts") {
+        for (a, b) in conflicting_pairs(&assignments) {
+            let describe = |assignment: Assignment| format!("line {} ({}-{})", assignment.line, assignment.sections.0, assignment.sections.1);
+            println!("  {} and {}", describe(assignments[a]), describe(assignments[b]));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_camp_cleanup {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn generated_pairs_parse() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(4), 50);
+        let pairs: Vec<(ElfPair, ElfPair)> = parse_lines(&lines, |line| parse_elf_pair(line.to_owned())).unwrap();
+        assert_eq!(pairs.len(), 50);
+        assert!(pairs.iter().all(|(a, b)| a.0 <= a.1 && b.0 <= b.1));
+    }
+
+    #[test]
+    fn reversed_range_is_rejected() {
+        assert!(matches!(parse_elf_pair("5-3,1-2".to_string()), Err(PuzzleError::Validation { .. })));
+    }
 }
@@ -0,0 +1,160 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::camp_cleanup::ElfPair;
+
+/// The sections of one elf, with the line of the input it was assigned on.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Assignment {
+    pub line: usize,
+    pub sections: ElfPair,
+}
+
+/// Where assignments start and stop covering sections. An assignment stops the section after its
+/// last one, and at the same section stops are swept before starts, so ranges that only touch
+/// end to end do not overlap.
+fn events(assignments: &[Assignment]) -> Vec<(u64, bool, usize)> {
+    let mut events: Vec<(u64, bool, usize)> = assignments.iter().enumerate()
+        .flat_map(|(i, assignment)| [(assignment.sections.0 as u64, true, i), (assignment.sections.1 as u64 + 1, false, i)])
+        .collect();
+    events.sort_unstable();
+    events
+}
+
+/// How many elves cover each stretch of the camp, worked out with a sweep line over the starts and ends of the assignments.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Coverage {
+    /// (first section, last section, elves) for every stretch covered by at least one elf, in order
+    segments: Vec<(u64, u64, u32)>,
+    conflicts: u64,
+}
+
+impl Coverage {
+    pub fn new(assignments: &[Assignment]) -> Self {
+        let mut segments: Vec<(u64, u64, u32)> = Vec::new();
+        let mut conflicts: u64 = 0;
+        let mut depth: u32 = 0;
+        let mut depth_per_line: HashMap<usize, u32> = HashMap::new();
+        let mut since: u64 = 0;
+        for (section, starts, i) in events(assignments) {
+            if depth > 0 && section > since {
+                segments.push((since, section - 1, depth));
+            }
+            since = section;
+            let line: &mut u32 = depth_per_line.entry(assignments[i].line).or_default();
+            if starts {
+                // every elf already covering this section overlaps the new one
+                conflicts += (depth - *line) as u64;
+                depth += 1;
+                *line += 1;
+            } else {
+                depth -= 1;
+                *line -= 1;
+            }
+        }
+        Coverage { segments, conflicts }
+    }
+
+    /// Sections covered by at least one elf.
+    pub fn total(&self) -> u64 {
+        self.covered_by_at_least(1)
+    }
+
+    pub fn covered_by_at_least(&self, elves: u32) -> u64 {
+        self.segments.iter().filter(|(_, _, depth)| *depth >= elves).map(|(first, last, _)| last - first + 1).sum()
+    }
+
+    pub fn max_depth(&self) -> u32 {
+        self.segments.iter().map(|(_, _, depth)| *depth).max().unwrap_or(0)
+    }
+
+    /// The stretches of sections nobody covers, counting the camp from section 1 up to the last covered section.
+    pub fn uncovered(&self) -> Vec<(u64, u64)> {
+        let mut uncovered: Vec<(u64, u64)> = Vec::new();
+        let mut next: u64 = 1;
+        for (first, last, _) in self.segments.iter() {
+            if *first > next {
+                uncovered.push((next, first - 1));
+            }
+            next = next.max(last + 1);
+        }
+        uncovered
+    }
+
+    /// Pairs of elves from different lines whose assignments overlap.
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+}
+
+/// Every pair of overlapping assignments from different lines, as indices into `assignments`. Takes time in proportion to the number of pairs found.
+pub fn conflicting_pairs(assignments: &[Assignment]) -> Vec<(usize, usize)> {
+    let mut active: BTreeSet<usize> = BTreeSet::new();
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (_, starts, i) in events(assignments) {
+        if !starts {
+            active.remove(&i);
+            continue;
+        }
+        pairs.extend(active.iter().filter(|j| assignments[**j].line != assignments[i].line).map(|j| (*j.min(&i), *j.max(&i))));
+        active.insert(i);
+    }
+    pairs.sort_unstable();
+    pairs
+}
+
+#[cfg(test)]
+mod test_coverage {
+    use super::*;
+
+    fn example() -> Vec<Assignment> {
+        [((2, 4), (6, 8)), ((2, 3), (4, 5)), ((5, 7), (7, 9)), ((2, 8), (3, 7)), ((6, 6), (4, 6)), ((2, 6), (4, 8))].into_iter().enumerate()
+            .flat_map(|(i, (a, b))| [Assignment { line: i + 1, sections: a }, Assignment { line: i + 1, sections: b }])
+            .collect()
+    }
+
+    #[test]
+    fn coverage_of_the_example() {
+        let coverage: Coverage = Coverage::new(&example());
+        assert_eq!(coverage.total(), 8);
+        assert_eq!(coverage.uncovered(), vec![(1, 1)]);
+        assert_eq!(coverage.max_depth(), 8);
+        assert_eq!(coverage.covered_by_at_least(8), 1);
+        assert_eq!(coverage.covered_by_at_least(7), 3);
+    }
+
+    #[test]
+    fn gaps_between_assignments_are_uncovered() {
+        let assignments: Vec<Assignment> = vec![Assignment { line: 1, sections: (3, 4) }, Assignment { line: 2, sections: (8, 10) }, Assignment { line: 3, sections: (9, 12) }];
+        let coverage: Coverage = Coverage::new(&assignments);
+        assert_eq!(coverage.uncovered(), vec![(1, 2), (5, 7)]);
+        assert_eq!(coverage.total(), 2 + 5);
+        assert_eq!(coverage.covered_by_at_least(2), 2);
+        assert_eq!(coverage.conflicts(), 1);
+    }
+
+    #[test]
+    fn touching_ranges_do_not_conflict() {
+        let assignments: Vec<Assignment> = vec![Assignment { line: 1, sections: (1, 4) }, Assignment { line: 2, sections: (5, 6) }, Assignment { line: 3, sections: (6, 6) }];
+        assert_eq!(Coverage::new(&assignments).conflicts(), 1);
+        assert_eq!(conflicting_pairs(&assignments), vec![(1, 2)]);
+        assert_eq!(Coverage::new(&assignments).max_depth(), 2);
+    }
+
+    #[test]
+    fn pairs_on_the_same_line_are_not_conflicts() {
+        let assignments: Vec<Assignment> = vec![Assignment { line: 1, sections: (1, 4) }, Assignment { line: 1, sections: (2, 6) }];
+        assert_eq!(Coverage::new(&assignments).conflicts(), 0);
+        assert_eq!(Coverage::new(&assignments).max_depth(), 2);
+    }
+
+    #[test]
+    fn conflict_count_matches_the_pairs() {
+        let assignments: Vec<Assignment> = example();
+        let brute_force: u64 = (0..assignments.len()).flat_map(|i| (i + 1..assignments.len()).map(move |j| (i, j)))
+            .filter(|(i, j)| assignments[*i].line != assignments[*j].line)
+            .filter(|(i, j)| assignments[*i].sections.0 <= assignments[*j].sections.1 && assignments[*j].sections.0 <= assignments[*i].sections.1)
+            .count() as u64;
+        assert_eq!(Coverage::new(&assignments).conflicts(), brute_force);
+        assert_eq!(conflicting_pairs(&assignments).len() as u64, brute_force);
+    }
+}
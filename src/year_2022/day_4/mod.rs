@@ -1 +1,2 @@
 pub mod camp_cleanup;
+pub mod coverage;
@@ -0,0 +1,157 @@
+use std::str::FromStr;
+
+use crate::PuzzleError;
+
+use super::supply_stacks::Stack;
+
+/// A crane model, deciding how `amount` crates get from the top of `source` to the top of `destination`.
+pub trait Crane {
+    fn name(&self) -> String;
+
+    fn migrate(&self, source: Stack, destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError>;
+
+    /// Same as `migrate`, but moves the crates between the two stacks where they are, without copying either stack.
+    fn migrate_in_place(&self, source: &mut Stack, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError>;
+}
+
+/// Lifts one crate at a time, so the moved crates end up in reverse order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_string()
+    }
+
+    fn migrate(&self, source: Stack, destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError> {
+        source.migrate_async(destination, amount)
+    }
+
+    fn migrate_in_place(&self, source: &mut Stack, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError> {
+        source.move_one_by_one(destination, amount)
+    }
+}
+
+/// Lifts all the crates at once, keeping their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_string()
+    }
+
+    fn migrate(&self, source: Stack, destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError> {
+        source.migrate_sync(destination, amount)
+    }
+
+    fn migrate_in_place(&self, source: &mut Stack, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError> {
+        source.move_together(destination, amount)
+    }
+}
+
+/// Lifts up to `max_lift` crates at once, keeping their order, and splits larger moves into several lifts.
+pub struct MaxLift {
+    pub max_lift: usize,
+}
+
+impl Crane for MaxLift {
+    fn name(&self) -> String {
+        format!("crane lifting up to {}", self.max_lift)
+    }
+
+    fn migrate(&self, mut source: Stack, mut destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError> {
+        let mut remaining: usize = amount;
+        while remaining > 0 {
+            let lift: usize = remaining.min(self.max_lift);
+            (source, destination) = source.migrate_sync(destination, lift)?;
+            remaining -= lift;
+        }
+        Ok((source, destination))
+    }
+
+    fn migrate_in_place(&self, source: &mut Stack, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError> {
+        source.take(amount)?;
+        // the first lift takes the top crates and lands lowest, every later lift goes on top of it
+        let lifts: Vec<usize> = (0..amount).step_by(self.max_lift).map(|lifted| self.max_lift.min(amount - lifted)).collect();
+        for lift in lifts {
+            source.move_together(destination, lift)?;
+        }
+        Ok(())
+    }
+}
+
+/// A crane picked by name on the command line: `9000`, `9001` or `max-lift:<crates>`.
+pub struct CraneModel(pub Box<dyn Crane>);
+
+impl FromStr for CraneModel {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "9000" => Ok(CraneModel(Box::new(CrateMover9000))),
+            None if s == "9001" => Ok(CraneModel(Box::new(CrateMover9001))),
+            Some(("max-lift", max_lift)) => match max_lift.parse::<usize>() {
+                Ok(max_lift) if max_lift > 0 => Ok(CraneModel(Box::new(MaxLift { max_lift }))),
+                _ => Err(PuzzleError::parse(format!("A crane must lift at least one crate, found {max_lift}"))),
+            },
+            _ => Err(PuzzleError::parse(format!("Unknown crane {s}, expected 9000, 9001 or max-lift:<crates>"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_crane {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::year_2022::day_5::supply_stacks::Crate;
+
+    fn crates(labels: &str) -> VecDeque<Crate> {
+        labels.chars().map(String::from).collect()
+    }
+
+    fn stacks() -> (Stack, Stack) {
+        (Stack { crates: crates("ABCDE") }, Stack { crates: crates("Z") })
+    }
+
+    #[test]
+    fn max_lift_splits_large_moves() {
+        let (source, destination) = stacks();
+        let (source, destination) = MaxLift { max_lift: 2 }.migrate(source, destination, 5).unwrap();
+        assert!(source.crates.is_empty());
+        assert_eq!(destination.crates, crates("ECDABZ"));
+    }
+
+    #[test]
+    fn max_lift_matches_the_crate_movers() {
+        let moved = |crane: &dyn Crane| {
+            let (source, destination) = stacks();
+            crane.migrate(source, destination, 4).unwrap()
+        };
+        assert_eq!(moved(&MaxLift { max_lift: 1 }), moved(&CrateMover9000));
+        assert_eq!(moved(&MaxLift { max_lift: 4 }), moved(&CrateMover9001));
+    }
+
+    #[test]
+    fn in_place_matches_migrate() {
+        let cranes: [Box<dyn Crane>; 4] = [Box::new(CrateMover9000), Box::new(CrateMover9001), Box::new(MaxLift { max_lift: 2 }), Box::new(MaxLift { max_lift: 9 })];
+        for crane in cranes {
+            for amount in 0..=5 {
+                let (mut source, mut destination) = stacks();
+                crane.migrate_in_place(&mut source, &mut destination, amount).unwrap();
+                let (source_copy, destination_copy) = stacks();
+                assert_eq!((source, destination), crane.migrate(source_copy, destination_copy, amount).unwrap());
+            }
+            let (mut source, mut destination) = stacks();
+            assert!(crane.migrate_in_place(&mut source, &mut destination, 6).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_crane_models() {
+        assert_eq!("9000".parse::<CraneModel>().unwrap().0.name(), "CrateMover 9000");
+        assert_eq!("9001".parse::<CraneModel>().unwrap().0.name(), "CrateMover 9001");
+        assert_eq!("max-lift:3".parse::<CraneModel>().unwrap().0.name(), "crane lifting up to 3");
+        assert!("max-lift:0".parse::<CraneModel>().is_err());
+        assert!("9002".parse::<CraneModel>().is_err());
+    }
+}
@@ -1 +1,3 @@
+pub mod crane;
+pub mod planner;
 pub mod supply_stacks;
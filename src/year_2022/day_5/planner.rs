@@ -0,0 +1,140 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use itertools::Itertools;
+
+use crate::PuzzleError;
+
+use super::{crane::Crane, supply_stacks::{GameState, Instruction}};
+
+/// What the search found after looking at every plan up to the bound.
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum Plan {
+    /// A shortest list of instructions reaching the target.
+    Moves(Vec<Instruction>),
+    /// Every reachable arrangement was visited without meeting the target, so no plan exists at all.
+    Unreachable,
+    /// No plan of at most this many instructions reaches the target.
+    NoneWithin(usize),
+}
+
+/// Reads the drawing of the arrangement to reach, in the same format as the top of the puzzle input.
+pub(super) fn load_target(path: &Path) -> Result<GameState, PuzzleError> {
+    let contents: String = fs::read_to_string(path).map_err(|e| PuzzleError::execution(format!("Could not read the target arrangement {}: {e}", path.display())))?;
+    let drawing: Vec<String> = contents.lines().take_while(|line| !line.trim().is_empty()).map(String::from).collect();
+    GameState::parse(drawing).map_err(|e| e.with_path(path.display().to_string()))
+}
+
+/// Every instruction that can run on `state`: any number of crates from any stack onto any other stack.
+fn moves(state: &GameState) -> Vec<Instruction> {
+    let stacks: usize = state.stacks.len();
+    (1..=stacks).cartesian_product(1..=stacks)
+        .filter(|(source, destination)| source != destination)
+        .flat_map(|(source, destination)| (1..=state.stacks[source - 1].crates.len()).map(move |count| Instruction { count, source, destination }))
+        .collect()
+}
+
+/// Searches breadth first for the fewest instructions taking `start` to `target` with `crane`,
+/// looking at plans of at most `max_moves` instructions.
+pub(super) fn plan(start: &GameState, target: &GameState, crane: &dyn Crane, max_moves: usize) -> Result<Plan, PuzzleError> {
+    if start.stacks.len() != target.stacks.len() {
+        return Err(PuzzleError::validation(format!("The target has {} stacks but the starting arrangement has {}", target.stacks.len(), start.stacks.len())));
+    }
+    let crates = |state: &GameState| state.stacks.iter().flat_map(|stack| stack.crates.iter().cloned()).sorted().collect::<Vec<String>>();
+    if crates(start) != crates(target) {
+        return Err(PuzzleError::validation("The target does not hold the same crates as the starting arrangement"));
+    }
+    // every arrangement seen so far, with the arrangement and instruction it was first reached from
+    let mut reached_from: HashMap<GameState, Option<(GameState, Instruction)>> = HashMap::from([(start.clone(), None)]);
+    let mut frontier: Vec<GameState> = vec![start.clone()];
+    let mut depth: usize = 0;
+    while !reached_from.contains_key(target) {
+        if frontier.is_empty() {
+            return Ok(Plan::Unreachable);
+        }
+        if depth == max_moves {
+            return Ok(Plan::NoneWithin(max_moves));
+        }
+        let mut next: Vec<GameState> = Vec::new();
+        for state in frontier {
+            for instruction in moves(&state) {
+                let mut after: GameState = state.clone();
+                after.apply(&instruction, crane, false)?;
+                if !reached_from.contains_key(&after) {
+                    reached_from.insert(after.clone(), Some((state.clone(), instruction)));
+                    next.push(after);
+                }
+            }
+        }
+        frontier = next;
+        depth += 1;
+    }
+    let mut instructions: Vec<Instruction> = Vec::new();
+    let mut state: &GameState = target;
+    while let Some(Some((previous, instruction))) = reached_from.get(state) {
+        instructions.push(*instruction);
+        state = previous;
+    }
+    instructions.reverse();
+    Ok(Plan::Moves(instructions))
+}
+
+#[cfg(test)]
+mod test_planner {
+    use super::*;
+    use crate::year_2022::day_5::crane::{CrateMover9000, CrateMover9001, MaxLift};
+
+    fn state(rows: &[&str]) -> GameState {
+        GameState::parse(rows.iter().map(|row| row.to_string()).collect()).unwrap()
+    }
+
+    fn start() -> GameState {
+        state(&["[A]        ", "[B] [C]    ", " 1   2   3 "])
+    }
+
+    fn target() -> GameState {
+        state(&["    [A]    ", "    [B]    ", "    [C]    ", " 1   2   3 "])
+    }
+
+    fn run(start: &GameState, instructions: &[Instruction], crane: &dyn Crane) -> GameState {
+        instructions.iter().fold(start.clone(), |mut state, instruction| {
+            state.apply(instruction, crane, false).unwrap();
+            state
+        })
+    }
+
+    #[test]
+    fn start_needs_no_moves() {
+        assert_eq!(plan(&start(), &start(), &CrateMover9000, 3), Ok(Plan::Moves(vec![])));
+    }
+
+    #[test]
+    fn crane_model_changes_the_plan() {
+        let target: GameState = target();
+        // the 9001 keeps the order, the 9000 reverses it and has to move the crates twice
+        let Ok(Plan::Moves(moves)) = plan(&start(), &target, &CrateMover9001, 5) else { panic!() };
+        assert_eq!(moves, vec![Instruction { count: 2, source: 1, destination: 2 }]);
+        let Ok(Plan::Moves(moves)) = plan(&start(), &target, &CrateMover9000, 5) else { panic!() };
+        assert_eq!(moves.len(), 2);
+        assert_eq!(run(&start(), &moves, &CrateMover9000), target);
+    }
+
+    #[test]
+    fn bound_is_respected() {
+        let target: GameState = target();
+        assert_eq!(plan(&start(), &target, &CrateMover9000, 1), Ok(Plan::NoneWithin(1)));
+        assert_eq!(plan(&start(), &target, &MaxLift { max_lift: 1 }, 3).map(|plan| matches!(plan, Plan::Moves(_))), Ok(true));
+    }
+
+    #[test]
+    fn exhausted_search_proves_unreachable() {
+        // a single stack has nowhere to put its crates, so its order can never change
+        let one_stack: GameState = state(&["[A]", "[B]", " 1 "]);
+        assert_eq!(plan(&one_stack, &state(&["[B]", "[A]", " 1 "]), &CrateMover9000, 50), Ok(Plan::Unreachable));
+    }
+
+    #[test]
+    fn different_crates_are_rejected() {
+        assert!(plan(&start(), &state(&["[A] [D] [C]", " 1   2   3 "]), &CrateMover9000, 3).is_err());
+        assert!(plan(&start(), &state(&["[A]    ", "[B] [C]", " 1   2 "]), &CrateMover9000, 3).is_err());
+    }
+}
@@ -1,68 +1,133 @@
-use std::{str::FromStr, vec, collections::VecDeque};
+use std::{str::FromStr, vec, collections::VecDeque, fmt::Display, hash::{Hash, Hasher}, path::Path};
 
-use itertools::Itertools;
 use lazy_static::lazy_static;
+use rand::{rngs::StdRng, RngExt};
 use regex::Regex;
 
-use crate::ParseInputError;
+use crate::{animation::Frame, cli::Args, differential::Variant, error::parse_lines, PuzzleError};
 
-#[derive(Debug, PartialEq, Eq)]
-struct ParseGameStateError;
+use super::{crane::{Crane, CraneModel, CrateMover9000, CrateMover9001}, planner::{self, Plan}};
 
-#[derive(Debug, PartialEq, Eq)]
-struct ParseInstructionError;
+/// The label of a crate, written between brackets in the drawing.
+pub(super) type Crate = String;
 
-#[derive(Debug, PartialEq, Eq)]
-struct ExecutionError;
-
-
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct Stack {
-    crates: VecDeque<char>,
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct Stack {
+    pub(super) crates: VecDeque<Crate>,
 }
 
 impl Stack {
-    fn migrate_async(self, destination: Stack, amount: usize) -> Result<(Stack, Stack), ExecutionError> {
-        let mut source_crates: VecDeque<char> = self.crates;
-        let mut destination_crates: VecDeque<char> = destination.crates;
-        source_crates.drain(0..amount).into_iter().for_each(|e: char| destination_crates.push_front(e));
+    pub(super) fn take(&self, amount: usize) -> Result<(), PuzzleError> {
+        match amount <= self.crates.len() {
+            true => Ok(()),
+            false => Err(PuzzleError::execution(format!("Cannot take {amount} crates from a stack of {}", self.crates.len()))),
+        }
+    }
+
+    pub(super) fn migrate_async(self, destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError> {
+        self.take(amount)?;
+        let mut source_crates: VecDeque<Crate> = self.crates;
+        let mut destination_crates: VecDeque<Crate> = destination.crates;
+        source_crates.drain(0..amount).for_each(|e: Crate| destination_crates.push_front(e));
         Ok((Stack{ crates: source_crates }, Stack{ crates: destination_crates }))
     }
 
-    fn migrate_sync(self, destination: Stack, amount: usize) -> Result<(Stack, Stack), ExecutionError> {
-        let mut source_crates: VecDeque<char> = self.crates;
-        let mut destination_crates: VecDeque<char> = destination.crates;
-        let mut migrating_crates: VecDeque<char> = source_crates.drain(0..amount).collect();
+    pub(super) fn migrate_sync(self, destination: Stack, amount: usize) -> Result<(Stack, Stack), PuzzleError> {
+        self.take(amount)?;
+        let mut source_crates: VecDeque<Crate> = self.crates;
+        let mut destination_crates: VecDeque<Crate> = destination.crates;
+        let mut migrating_crates: VecDeque<Crate> = source_crates.drain(0..amount).collect();
         migrating_crates.append(&mut destination_crates);
         Ok((Stack{ crates: source_crates }, Stack{ crates: migrating_crates }))
     }
+
+    /// In place version of `migrate_async`, only touching the crates that move.
+    pub(super) fn move_one_by_one(&mut self, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError> {
+        self.take(amount)?;
+        self.crates.drain(0..amount).for_each(|e: Crate| destination.crates.push_front(e));
+        Ok(())
+    }
+
+    /// In place version of `migrate_sync`, only touching the crates that move.
+    pub(super) fn move_together(&mut self, destination: &mut Stack, amount: usize) -> Result<(), PuzzleError> {
+        self.take(amount)?;
+        self.crates.drain(0..amount).rev().for_each(|e: Crate| destination.crates.push_front(e));
+        Ok(())
+    }
 }
 
 
-#[derive(Debug, PartialEq, Eq)]
-struct Instruction {
-    count: usize,
-    source: usize,
-    destination: usize,
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(super) struct Instruction {
+    pub(super) count: usize,
+    pub(super) source: usize,
+    pub(super) destination: usize,
 }
 
 impl Instruction {
-    fn execute(&self, state: GameState, migrate: impl Fn(Stack, Stack, usize) -> Result<(Stack, Stack), ExecutionError>) -> Result<GameState, ExecutionError> {
-        if let (Some(source_stack), Some(destination_stack)) = (state.stacks.get(self.source - 1), state.stacks.get(self.destination - 1)) {
-            if let Ok((new_source_stack, new_destination_stack)) = migrate(source_stack.clone(), destination_stack.clone(), self.count) {
-                // quite unsafe - can this be improved?
-                let mut stacks = state.stacks.clone();
-                let _ = std::mem::replace(&mut stacks[self.source - 1], new_source_stack);
-                let _ = std::mem::replace(&mut stacks[self.destination - 1], new_destination_stack);
-                return Ok(GameState { stacks });
-            }
+    /// The instruction as it can run on `state`. Lenient instructions move as many crates as the
+    /// source stack holds, strict ones fail instead.
+    fn validate(&self, state: &GameState, lenient: bool) -> Result<Instruction, PuzzleError> {
+        let stacks: usize = state.stacks.len();
+        if let Some(stack) = [self.source, self.destination].into_iter().find(|stack| *stack == 0 || *stack > stacks) {
+            return Err(PuzzleError::execution(format!("there is no stack {stack}, the stacks are numbered 1 to {stacks}")));
+        }
+        if self.source == self.destination {
+            return Err(PuzzleError::execution(format!("stack {} cannot be moved onto itself", self.source)));
         }
-        Err(ExecutionError)
+        let available: usize = state.stacks[self.source - 1].crates.len();
+        match (self.count <= available, lenient) {
+            (true, _) => Ok(*self),
+            (false, true) => Ok(Instruction { count: available, ..*self }),
+            (false, false) => Err(PuzzleError::execution(format!("stack {} holds only {available} crates", self.source))),
+        }
+    }
+
+    fn execute(&self, state: GameState, crane: &dyn Crane) -> Result<GameState, PuzzleError> {
+        self.validate(&state, false)?;
+        let (new_source_stack, new_destination_stack) = crane.migrate(state.stacks[self.source - 1].clone(), state.stacks[self.destination - 1].clone(), self.count)?;
+        let mut stacks = state.stacks;
+        stacks[self.source - 1] = new_source_stack;
+        stacks[self.destination - 1] = new_destination_stack;
+        Ok(GameState { stacks, layout: state.layout })
+    }
+}
+
+/// Names instruction number `n` in its error and places it on `line`, the instruction as it was
+/// written, counting lines from the first instruction.
+fn failed(n: usize, instruction: &Instruction, line: &str, e: PuzzleError) -> PuzzleError {
+    PuzzleError::execution(format!("Instruction {n} ({instruction}) failed: {}", e.details())).at_line(n).with_text(line)
+}
+
+/// Runs instruction number `n`, written as `line`, on a copy of the stacks.
+fn step(state: GameState, n: usize, instruction: &Instruction, line: &str, crane: &dyn Crane, lenient: bool) -> Result<GameState, PuzzleError> {
+    instruction.validate(&state, lenient)
+        .and_then(|instruction| instruction.execute(state, crane))
+        .map_err(|e| failed(n, instruction, line, e))
+}
+
+// slow copying method, every instruction clones the whole arrangement
+fn run(state: &GameState, instructions: &[Instruction], lines: &[String], crane: &dyn Crane, lenient: bool) -> Result<GameState, PuzzleError> {
+    instructions.iter().enumerate().try_fold(state.clone(), |state: GameState, (i, instruction)| step(state, i + 1, instruction, &lines[i], crane, lenient))
+}
+
+/// Runs the instructions on the stacks where they are, so each move costs only the crates it moves.
+/// `lines` holds each instruction as it was written, for the errors.
+fn run_in_place(mut state: GameState, instructions: &[Instruction], lines: &[String], crane: &dyn Crane, lenient: bool) -> Result<GameState, PuzzleError> {
+    for (i, instruction) in instructions.iter().enumerate() {
+        state.apply(instruction, crane, lenient).map_err(|e| failed(i + 1, instruction, &lines[i], e))?;
+    }
+    Ok(state)
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.count, self.source, self.destination)
     }
 }
 
 impl FromStr for Instruction {
-    type Err = ParseInstructionError;
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
@@ -72,75 +137,344 @@ impl FromStr for Instruction {
             let parse = |n: usize| cap.get(n).unwrap().as_str().parse::<usize>().unwrap(); // capture passes => we know there are 3 integers in the string 
             return Ok(Instruction{count: parse(1), source: parse(2), destination: parse(3)}); // logic as to why the first element is the full?!
         }
-        Err(ParseInstructionError)
+        Err(PuzzleError::parse("Instruction is not of the form 'move n from a to b'"))
+    }
+}
+
+/// Where a drawing puts the stacks: the columns each stack number spans in the footer, counting
+/// from 0, and how wide the lines are.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Layout {
+    columns: Vec<(usize, usize)>,
+    line_width: usize,
+}
+
+/// The first column of `label` drawn as a crate, centred on the stack number spanning `column`.
+fn crate_start(column: (usize, usize), label: &Crate) -> usize {
+    (column.0 + column.1).div_ceil(2).saturating_sub(label.chars().count().div_ceil(2))
+}
+
+impl Layout {
+    /// Columns as wide as the widest crate or stack number, one space apart.
+    fn uniform(stacks: &[Stack]) -> Layout {
+        let width: usize = stacks.iter().flat_map(|stack| stack.crates.iter().map(|c| c.chars().count()))
+            .chain([stacks.len().to_string().len()])
+            .max().unwrap_or(1) + 2;
+        let columns: Vec<(usize, usize)> = (0..stacks.len()).map(|n| {
+            let digits: usize = (n + 1).to_string().len();
+            let first: usize = n * (width + 1) + (width - 1) / 2 - digits / 2;
+            (first, first + digits - 1)
+        }).collect();
+        Layout { columns, line_width: (stacks.len() * (width + 1)).saturating_sub(1) }
+    }
+
+    /// The rows of crates, topmost first, and the numbered footer.
+    fn draw(&self, stacks: &[Stack]) -> Vec<String> {
+        let line = |words: Vec<(usize, String)>| -> String {
+            let mut line: String = String::new();
+            for (start, word) in words {
+                line.push_str(&" ".repeat(start.saturating_sub(line.chars().count())));
+                line.push_str(&word);
+            }
+            format!("{line:<width$}", width = self.line_width)
+        };
+        let height: usize = stacks.iter().map(|stack| stack.crates.len()).max().unwrap_or(0);
+        let mut rows: Vec<String> = (0..height).rev().map(|level: usize| {
+            line(stacks.iter().zip(self.columns.iter()).filter_map(|(stack, column)| {
+                let c: &Crate = stack.crates.len().checked_sub(level + 1).and_then(|i| stack.crates.get(i))?;
+                Some((crate_start(*column, c), format!("[{c}]")))
+            }).collect())
+        }).collect();
+        rows.push(line(self.columns.iter().enumerate().map(|(n, (first, _))| (*first, (n + 1).to_string())).collect()));
+        rows
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct GameState {
-    stacks: Vec<Stack>,
+/// The stacks, along with the layout of the drawing they were read from. Two states are the same
+/// when their stacks are, however they were drawn.
+#[derive(Debug, Clone)]
+pub(super) struct GameState {
+    pub(super) stacks: Vec<Stack>,
+    layout: Layout,
+}
+
+impl PartialEq for GameState {
+    fn eq(&self, other: &Self) -> bool {
+        self.stacks == other.stacks
+    }
+}
+
+impl Eq for GameState {}
+
+impl Hash for GameState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.stacks.hash(state);
+    }
+}
+
+/// The words of a line with the columns they span, first and last column included, counting from 0.
+fn words(line: &str) -> Vec<(usize, usize, String)> {
+    let mut words: Vec<(usize, usize, String)> = Vec::new();
+    for (column, c) in line.chars().enumerate() {
+        match (c.is_whitespace(), words.last_mut()) {
+            (true, _) => {},
+            (false, Some((_, last, word))) if *last + 1 == column => {
+                *last = column;
+                word.push(c);
+            },
+            (false, _) => words.push((column, column, c.to_string())),
+        }
+    }
+    words
 }
 
 impl GameState {
-    fn _parse_stack_count(s: Option<&String>) -> Result<usize, ParseGameStateError> {
-        if let Some(footer) = s {
-            if let Some(x) = footer.chars().filter_map(|c: char| c.to_digit(10)).last() {
-                return Ok(x as usize);
+    pub(super) fn new(stacks: Vec<Stack>) -> GameState {
+        GameState { stacks, layout: Layout::default() }
+    }
+
+    /// The columns each stack number spans in the footer. The stacks must be numbered 1, 2, 3, ... from left to right.
+    fn _parse_footer(footer: &str) -> Result<Vec<(usize, usize)>, PuzzleError> {
+        let labels: Vec<(usize, usize, String)> = words(footer);
+        if labels.is_empty() {
+            return Err(PuzzleError::parse("Stack footer does not contain any stack numbers"));
+        }
+        for (n, (first, _, label)) in labels.iter().enumerate() {
+            if *label != (n + 1).to_string() {
+                return Err(PuzzleError::parse(format!("Expected stack number {} but found {label}", n + 1)).at_column(first + 1));
+            }
+        }
+        Ok(labels.into_iter().map(|(first, last, _)| (first, last)).collect())
+    }
+
+    /// The crates of one row of the drawing, by stack. A crate belongs to the stack whose number it is drawn above.
+    fn _parse_row(row: &str, columns: &[(usize, usize)]) -> Result<Vec<Option<Crate>>, PuzzleError> {
+        let mut crates: Vec<Option<Crate>> = vec![None; columns.len()];
+        for (first, last, word) in words(row) {
+            let label: &str = word.strip_prefix('[').and_then(|word| word.strip_suffix(']'))
+                .filter(|label| !label.is_empty() && !label.contains(['[', ']']))
+                .ok_or(PuzzleError::parse(format!("Expected a crate such as [A], found {word}")).at_column(first + 1))?;
+            let below: Vec<usize> = (0..columns.len()).filter(|n| columns[*n].0 <= last && first <= columns[*n].1).collect();
+            match below[..] {
+                [n] if crates[n].is_none() => crates[n] = Some(label.to_string()),
+                [n] => return Err(PuzzleError::parse(format!("Crates {} and {word} are both drawn above stack {}", crates[n].as_ref().unwrap(), n + 1)).at_column(first + 1)),
+                _ => return Err(PuzzleError::parse(format!("Crate {word} is not drawn above exactly one stack number")).at_column(first + 1)),
             }
         }
-        Err(ParseGameStateError)
+        Ok(crates)
     }
 
-    fn _parse_crates(s: Vec<String>, stack_count: usize) -> Vec<Vec<char>> {
-        let mut stacks: Vec<Vec<char>> = Vec::new();
-        for line in &s[..stack_count].to_vec() {
-            for (n, chunk) in line.chars().collect::<Vec<char>>().chunks(4).enumerate() {
-                match stacks.get_mut(n) {
-                    Some(stack) => stack.push(chunk[1]),
-                    None => stacks.insert(n, vec![chunk[1]]),
+    pub(super) fn parse(s: Vec<String>) -> Result<Self, PuzzleError> {
+        let footer: &String = s.last().ok_or(PuzzleError::parse("Stack footer does not contain any stack numbers"))?;
+        let columns: Vec<(usize, usize)> = GameState::_parse_footer(footer).map_err(|e| e.at_line(s.len()).with_text(footer))?;
+        // every row above the footer holds crates, stacked from the bottom row up
+        let mut stacks: Vec<Vec<Crate>> = vec![Vec::new(); columns.len()];
+        for (i, row) in s[..s.len() - 1].iter().enumerate().rev() {
+            let tagged = |e: PuzzleError| e.at_line(i + 1).with_text(row);
+            for (n, label) in GameState::_parse_row(row, &columns).map_err(tagged)?.into_iter().enumerate() {
+                match label {
+                    Some(label) if stacks[n].len() + i + 2 < s.len() => return Err(tagged(PuzzleError::parse(format!("Crate [{label}] floats above an empty space in stack {}", n + 1)))),
+                    Some(label) => stacks[n].push(label),
+                    None => {},
                 }
             }
         }
-        stacks
+        let stacks: Vec<Stack> = stacks.into_iter().map(|stack| Stack { crates: stack.into_iter().rev().collect() }).collect();
+        Ok(GameState { stacks, layout: Layout { columns, line_width: footer.chars().count() } })
+    }
+
+    pub(super) fn apply(&mut self, instruction: &Instruction, crane: &dyn Crane, lenient: bool) -> Result<(), PuzzleError> {
+        let instruction: Instruction = instruction.validate(self, lenient)?;
+        let [source, destination] = self.stacks.get_disjoint_mut([instruction.source - 1, instruction.destination - 1])
+            .map_err(|e| PuzzleError::execution(e.to_string()))?;
+        crane.migrate_in_place(source, destination, instruction.count)
     }
 
-    fn parse(s: Vec<String>) -> Result<Self, ParseGameStateError> {
-        if let Ok(stack_count) = GameState::_parse_stack_count(s.last()) {
-            let create_stack = |stack: Vec<char>| Stack { crates: stack.into_iter().skip_while(|c| c == &' ').collect() };
-            let stacks = GameState::_parse_crates(s, stack_count).into_iter().map(create_stack).collect();
-            return Ok(GameState { stacks });
+    fn top_crates(&self) -> Vec<Crate> {
+        self.stacks.iter().map(|stack: &Stack| stack.crates.front().cloned().unwrap_or(" ".to_string())).collect()
+    }
+
+    /// Draws the stacks as in the puzzle input, topmost crates first, with the numbered footer. The
+    /// layout of the parsed drawing is kept for as long as it reads back as the same stacks, e.g.
+    /// until a wide crate lands on a narrow column, after which every column is drawn as wide as
+    /// the widest crate or stack number.
+    fn crane_view(&self) -> Vec<String> {
+        let rows: Vec<String> = self.layout.draw(&self.stacks);
+        match GameState::parse(rows.clone()) {
+            Ok(state) if state == *self => rows,
+            _ => Layout::uniform(&self.stacks).draw(&self.stacks),
         }
-        Err(ParseGameStateError)
     }
+}
 
-    fn top_crates(&self) -> Vec<char> {
-        self.stacks.iter().map(|stack: &Stack| stack.crates.get(0).unwrap_or(&' ').to_owned()).collect()
+/// The drawing of the puzzle input, which `GameState::parse` reads back into the same state.
+impl Display for GameState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.crane_view().join("\n"))
     }
 }
 
-fn divide_stack_instruction(input: Vec<String>) -> Result<(GameState, Vec<Instruction>), ParseInputError> {
-    if let Some((state_input, instruction_input)) = input.split(|line| line == "").collect_tuple::<(&[String], &[String])>() {
-        if let Ok(state) = GameState::parse(state_input.to_vec()) {
-            let instructions: Vec<Instruction> = instruction_input.into_iter().filter_map(|instruction| instruction.parse::<Instruction>().ok()).collect();
-            return Ok((state, instructions));
-        }
+fn crane_frame(state: &GameState, carrying: &[Crate], caption: String) -> Frame {
+    let hook: String = carrying.iter().map(|c| format!("[{c}]")).collect();
+    let mut lines: Vec<String> = vec![format!("crane: {hook}"), String::new()];
+    lines.extend(state.crane_view());
+    Frame { caption, lines }
+}
+
+/// Frames of the CrateMover 9000 working through the instructions: each move is shown once with
+/// the crates hanging from the crane and once after they have been put down.
+pub fn animate(lines: Vec<String>) -> Result<Vec<Frame>, PuzzleError> {
+    let (mut state, instructions) = divide_stack_instruction(lines.clone())?;
+    let mut frames: Vec<Frame> = vec![crane_frame(&state, &[], "initial arrangement".to_string())];
+    for (i, instruction) in instructions.iter().enumerate() {
+        let next_state: GameState = instruction.execute(state.clone(), &CrateMover9000)
+            .map_err(|e| failed(i + 1, instruction, &instruction_lines(&lines)[i], e).offset_lines(instructions_start(&lines).unwrap_or(1) - 1))?;
+        let mut lifted: GameState = state;
+        let carrying: Vec<Crate> = lifted.stacks[instruction.source - 1].crates.drain(0..instruction.count).collect();
+        frames.push(crane_frame(&lifted, &carrying, format!("{}/{}: {instruction}", i + 1, instructions.len())));
+        frames.push(crane_frame(&next_state, &[], format!("{}/{}: {instruction}", i + 1, instructions.len())));
+        state = next_state;
+    }
+    Ok(frames)
+}
+
+/// The line the instructions start on, after the drawing and the blank line below it.
+fn instructions_start(input: &[String]) -> Option<usize> {
+    input.iter().position(|line| line.is_empty()).map(|blank| blank + 2)
+}
+
+/// The lines of the instructions as they were written, the first one being instruction 1.
+fn instruction_lines(input: &[String]) -> &[String] {
+    instructions_start(input).map_or(&[], |start| &input[start - 1..])
+}
+
+fn divide_stack_instruction(input: Vec<String>) -> Result<(GameState, Vec<Instruction>), PuzzleError> {
+    if let Some(start) = instructions_start(&input) {
+        let state: GameState = GameState::parse(input[..start - 2].to_vec())?;
+        let instruction_input: &[String] = &input[start - 1..];
+        // a file ending in a newline leaves an empty last line
+        let end: usize = instruction_input.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1);
+        let instructions: Vec<Instruction> = parse_lines(&instruction_input[..end], |instruction| instruction.parse::<Instruction>())
+            .map_err(|e| e.offset_lines(start - 1))?;
+        return Ok((state, instructions));
+    }
+    Err(PuzzleError::parse("Could not split the stack and instruction"))
+}
+
+/// Generates nine stacks of up to eight crates followed by `size` moves. The moves are played
+/// out while generating so that no move ever takes more crates than its source stack holds.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let stacks: Vec<Stack> = (0..9).map(|_| {
+        let height: usize = rng.random_range(0..=8);
+        Stack { crates: (0..height).map(|_| rng.random_range('A'..='Z').to_string()).collect() }
+    }).collect();
+    let mut heights: Vec<usize> = stacks.iter().map(|stack| stack.crates.len()).collect();
+    if heights.iter().all(|height| *height == 0) {
+        return generate(rng, size);
+    }
+    let mut lines: Vec<String> = GameState::new(stacks).crane_view();
+    lines.push(String::new());
+    for _ in 0..size {
+        let source: usize = loop {
+            let source: usize = rng.random_range(0..heights.len());
+            if heights[source] > 0 { break source; }
+        };
+        let destination: usize = (source + rng.random_range(1..heights.len())) % heights.len();
+        let count: usize = rng.random_range(1..=heights[source]);
+        heights[source] -= count;
+        heights[destination] += count;
+        lines.push(Instruction { count, source: source + 1, destination: destination + 1 }.to_string());
     }
-    Err(ParseInputError {details: "Could not split the stack and instruction".to_string()})
+    lines
+}
+
+fn top_crates_after(lines: &[String], crane: &dyn Crane, lenient: bool) -> Result<String, PuzzleError> {
+    let (state, instructions) = divide_stack_instruction(lines.to_vec())?;
+    let final_state: GameState = run_in_place(state, &instructions, instruction_lines(lines), crane, lenient).map_err(|e| e.offset_lines(instructions_start(lines).unwrap_or(1) - 1))?;
+    Ok(final_state.top_crates().concat())
+}
+
+/// Moving the crates in place and folding over copies of the arrangement both find the top crates of both crate movers.
+pub fn crane_variants() -> [Variant<[String; 2]>; 2] {
+    [
+        Variant { name: "in place", answer: |lines| {
+            Ok([top_crates_after(lines, &CrateMover9000, false)?, top_crates_after(lines, &CrateMover9001, false)?])
+        }},
+        Variant { name: "copying fold", answer: |lines| {
+            let (state, instructions) = divide_stack_instruction(lines.to_vec())?;
+            let top_crates = |crane: &dyn Crane| run(&state, &instructions, instruction_lines(lines), crane, false).map(|state| state.top_crates().concat());
+            Ok([top_crates(&CrateMover9000)?, top_crates(&CrateMover9001)?])
+        }},
+    ]
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    println!("{}", top_crates_after(&lines, &CrateMover9000, false)?);
+    println!("{}", top_crates_after(&lines, &CrateMover9001, false)?);
+    Ok(())
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok((state, instructions)) = divide_stack_instruction(lines) {
-        let final_state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, Stack::migrate_async).unwrap());
-        println!("{}", String::from_iter(final_state.top_crates()));
-        let final_state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, Stack::migrate_sync).unwrap());
-        println!("{}", String::from_iter(final_state.top_crates()));
+/// Prints the arrangement before the first instruction and after every one of them.
+fn dump_states(lines: &[String], crane: &dyn Crane, lenient: bool) -> Result<(), PuzzleError> {
+    let (mut state, instructions) = divide_stack_instruction(lines.to_vec())?;
+    println!("{state}");
+    for (i, instruction) in instructions.iter().enumerate() {
+        state.apply(instruction, crane, lenient).map_err(|e| failed(i + 1, instruction, &instruction_lines(lines)[i], e).offset_lines(instructions_start(lines).unwrap_or(1) - 1))?;
+        println!("\n{}: {instruction}\n{state}", i + 1);
     }
+    Ok(())
+}
+
+/// Searches for the fewest instructions taking the drawing at the top of `lines` to the arrangement drawn in `target`.
+fn print_plan(lines: &[String], target: &Path, crane: &dyn Crane, max_moves: usize) -> Result<(), PuzzleError> {
+    let (start, _) = divide_stack_instruction(lines.to_vec())?;
+    match planner::plan(&start, &planner::load_target(target)?, crane, max_moves)? {
+        Plan::Moves(instructions) => {
+            println!("{} reaches the target in {} moves", crane.name(), instructions.len());
+            instructions.iter().for_each(|instruction| println!("{instruction}"));
+        },
+        Plan::Unreachable => println!("{} can never reach the target", crane.name()),
+        Plan::NoneWithin(max_moves) => println!("{} cannot reach the target in {max_moves} moves or fewer", crane.name()),
+    }
+    Ok(())
+}
+
+/// Runs the instructions with the crane picked with `--crane`, or with both crate movers as in the
+/// puzzle. With `--lenient` a move takes as many crates as the stack holds instead of failing, and
+/// `--dump` prints the arrangement after every instruction. `--plan <file>` instead searches for the
+/// shortest instructions reaching the arrangement drawn in the file, trying up to `--max-moves` moves.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    if let Some(target) = args.get::<String>("plan")? {
+        let max_moves: usize = args.get("max-moves")?.unwrap_or(8);
+        return match args.get::<CraneModel>("crane")? {
+            Some(CraneModel(crane)) => print_plan(&lines, Path::new(&target), crane.as_ref(), max_moves),
+            None => print_plan(&lines, Path::new(&target), &CrateMover9000, max_moves),
+        };
+    }
+    let lenient: bool = args.has("lenient");
+    match args.get::<CraneModel>("crane")? {
+        Some(CraneModel(crane)) if args.has("dump") => dump_states(&lines, crane.as_ref(), lenient)?,
+        None if args.has("dump") => dump_states(&lines, &CrateMover9000, lenient)?,
+        Some(CraneModel(crane)) => println!("{}: {}", crane.name(), top_crates_after(&lines, crane.as_ref(), lenient)?),
+        None => {
+            println!("{}", top_crates_after(&lines, &CrateMover9000, lenient)?);
+            println!("{}", top_crates_after(&lines, &CrateMover9001, lenient)?);
+        },
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod supply_stacks {
+    use rand::SeedableRng;
+
     use crate::read_problem_input_file;
     use crate::year_2022::day_5::supply_stacks::{*};
+    use crate::year_2022::day_5::crane::MaxLift;
+
+    fn crates(labels: &str) -> VecDeque<Crate> {
+        labels.chars().map(String::from).collect()
+    }
 
     #[test]
     fn test_parse_instruction() {
@@ -151,7 +485,7 @@ mod supply_stacks {
     #[test]
     fn test_parse_state() {
         let input = vec!["    [D]    ".to_owned(), "[N] [C]    ".to_owned(), "[Z] [M] [P]".to_owned(), " 1   2   3 ".to_owned()];
-        assert_eq!(GameState::parse(input).unwrap(), GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['N', 'Z'])}, Stack{crates: VecDeque::from(vec!['D', 'C', 'M'])}, Stack{crates:VecDeque::from(vec!['P'])}]});
+        assert_eq!(GameState::parse(input).unwrap(), GameState::new(vec![Stack{crates: crates("NZ")}, Stack{crates: crates("DCM")}, Stack{crates:crates("P")}]));
     }
 
     #[test]
@@ -159,7 +493,7 @@ mod supply_stacks {
         let input = read_problem_input_file("inputs/2022/5/practice.txt".to_owned());
         let (state, _) = divide_stack_instruction(input).unwrap();
         
-        assert_eq!(state, GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['N', 'Z'])}, Stack{crates: VecDeque::from(vec!['D', 'C', 'M'])}, Stack{crates: VecDeque::from(vec!['P'])}]});
+        assert_eq!(state, GameState::new(vec![Stack{crates: crates("NZ")}, Stack{crates: crates("DCM")}, Stack{crates: crates("P")}]));
     }
 
     #[test]
@@ -175,48 +509,237 @@ mod supply_stacks {
         ]);
     }
 
+    #[test]
+    fn test_parse_more_rows_than_stacks() {
+        let lines: Vec<String> = vec!["[D]    ", "[N] [C]", "[Z] [M]", " 1   2 "].into_iter().map(String::from).collect();
+        let state: GameState = GameState::parse(lines).unwrap();
+        assert_eq!(state.stacks[0].crates, crates("DNZ"));
+        assert_eq!(state.stacks[1].crates, crates("CM"));
+    }
+
+    #[test]
+    fn test_generated_moves_are_legal() {
+        for seed in 0..20 {
+            let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(seed), 50);
+            let (state, instructions) = divide_stack_instruction(lines).unwrap();
+            assert_eq!(instructions.len(), 50);
+            assert!(instructions.iter().try_fold(state, |state, instruction| instruction.execute(state, &CrateMover9001)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_crane_view() {
+        let state = GameState::new(vec![Stack{crates: crates("NZ")}, Stack{crates: crates("DCM")}, Stack{crates:crates("P")}]);
+        assert_eq!(state.crane_view(), vec!["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", " 1   2   3 "]);
+    }
+
+    #[test]
+    fn test_animate_frames() {
+        let input: Vec<String> = vec!["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", " 1   2   3 ", "", "move 1 from 2 to 1", "move 3 from 1 to 3"].into_iter().map(String::from).collect();
+        let frames = animate(input).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[1].lines[0], "crane: [D]");
+        assert_eq!(frames[1].caption, "1/2: move 1 from 2 to 1");
+        assert_eq!(frames[3].lines[0], "crane: [D][N][Z]");
+        assert_eq!(frames[4].lines[2..], ["        [Z]", "        [N]", "    [C] [D]", "    [M] [P]", " 1   2   3 "]);
+    }
+
+    #[test]
+    fn test_animate_stops_at_bad_instruction() {
+        let input: Vec<String> = vec!["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", " 1   2   3 ", "", "move 1 from 4 to 1"].into_iter().map(String::from).collect();
+        let err: PuzzleError = animate(input.clone()).unwrap_err();
+        assert_eq!(err, top_crates_after(&input, &CrateMover9000, false).unwrap_err());
+        assert_eq!((err.location().line, err.location().text.as_deref()), (Some(6), Some("move 1 from 4 to 1")));
+    }
+
     #[test]
     fn test_execute_migrate_async() {
-        let (src, dest) = Stack{crates: VecDeque::from(vec!['D', 'N', 'Z'])}.migrate_async(Stack{crates: VecDeque::from(vec!['P'])}, 3).unwrap();
+        let (src, dest) = Stack{crates: crates("DNZ")}.migrate_async(Stack{crates: crates("P")}, 3).unwrap();
         assert_eq!(src, Stack{crates: VecDeque::new()});
-        assert_eq!(dest, Stack{crates: VecDeque::from(vec!['Z', 'N', 'D', 'P'])});
+        assert_eq!(dest, Stack{crates: crates("ZNDP")});
     }
 
     #[test]
     fn test_execute_migrate_sync() {
-        let (src, dest) = Stack{crates: VecDeque::from(vec!['D', 'N', 'Z'])}.migrate_sync(Stack{crates: VecDeque::from(vec!['P'])}, 3).unwrap();
+        let (src, dest) = Stack{crates: crates("DNZ")}.migrate_sync(Stack{crates: crates("P")}, 3).unwrap();
         assert_eq!(src, Stack{crates: VecDeque::new()});
-        assert_eq!(dest, Stack{crates: VecDeque::from(vec!['D', 'N', 'Z', 'P'])});
+        assert_eq!(dest, Stack{crates: crates("DNZP")});
     }
 
     #[test]
     fn test_execute_instruction_async() {
-        let state = GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['D', 'N', 'Z'])}, Stack{crates: VecDeque::from(vec!['C', 'M'])}, Stack{crates: VecDeque::from(vec!['P'])}]};
-        let new_state = Instruction{count: 3, source: 1, destination: 3}.execute(state, Stack::migrate_async).unwrap();
-        assert_eq!(new_state, GameState{stacks: vec![Stack{crates: VecDeque::new()}, Stack{crates: VecDeque::from(vec!['C', 'M'])}, Stack{crates: VecDeque::from(vec!['Z', 'N', 'D', 'P'])}]});
+        let state = GameState::new(vec![Stack{crates: crates("DNZ")}, Stack{crates: crates("CM")}, Stack{crates: crates("P")}]);
+        let new_state = Instruction{count: 3, source: 1, destination: 3}.execute(state, &CrateMover9000).unwrap();
+        assert_eq!(new_state, GameState::new(vec![Stack{crates: VecDeque::new()}, Stack{crates: crates("CM")}, Stack{crates: crates("ZNDP")}]));
     }
 
     #[test]
     fn test_execute_instruction_sync() {
-        let state = GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['D', 'N', 'Z'])}, Stack{crates: VecDeque::from(vec!['C', 'M'])}, Stack{crates: VecDeque::from(vec!['P'])}]};
-        let new_state = Instruction{count: 3, source: 1, destination: 3}.execute(state, Stack::migrate_sync).unwrap();
-        assert_eq!(new_state, GameState{stacks: vec![Stack{crates: VecDeque::new()}, Stack{crates: VecDeque::from(vec!['C', 'M'])}, Stack{crates: VecDeque::from(vec!['D', 'N', 'Z', 'P'])}]});
+        let state = GameState::new(vec![Stack{crates: crates("DNZ")}, Stack{crates: crates("CM")}, Stack{crates: crates("P")}]);
+        let new_state = Instruction{count: 3, source: 1, destination: 3}.execute(state, &CrateMover9001).unwrap();
+        assert_eq!(new_state, GameState::new(vec![Stack{crates: VecDeque::new()}, Stack{crates: crates("CM")}, Stack{crates: crates("DNZP")}]));
     }
 
     #[test]
     fn test_execute_solve_async() {
         let input = read_problem_input_file("inputs/2022/5/practice.txt".to_owned());
         let (state, instructions) = divide_stack_instruction(input).unwrap();
-        let state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, Stack::migrate_async).unwrap());
-        assert_eq!(state, GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['C'])}, Stack{crates: VecDeque::from(vec!['M'])}, Stack{crates: VecDeque::from(vec!['Z', 'N', 'D', 'P'])}]});
+        let state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, &CrateMover9000).unwrap());
+        assert_eq!(state, GameState::new(vec![Stack{crates: crates("C")}, Stack{crates: crates("M")}, Stack{crates: crates("ZNDP")}]));
     }
 
     #[test]
     fn test_execute_solve_sync() {
         let input = read_problem_input_file("inputs/2022/5/practice.txt".to_owned());
         let (state, instructions) = divide_stack_instruction(input).unwrap();
-        let state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, Stack::migrate_sync).unwrap());
-        assert_eq!(state, GameState{stacks: vec![Stack{crates: VecDeque::from(vec!['M'])}, Stack{crates: VecDeque::from(vec!['C'])}, Stack{crates: VecDeque::from(vec!['D', 'N', 'Z', 'P'])}]});
+        let state = instructions.iter().fold(state.clone(), |state: GameState, instruction: &Instruction| instruction.execute(state, &CrateMover9001).unwrap());
+        assert_eq!(state, GameState::new(vec![Stack{crates: crates("M")}, Stack{crates: crates("C")}, Stack{crates: crates("DNZP")}]));
+    }
+
+    fn example() -> Vec<String> {
+        vec!["    [D]    ", "[N] [C]    ", "[Z] [M] [P]", " 1   2   3 ", "", "move 1 from 2 to 1", "move 3 from 1 to 3", "move 2 from 2 to 1", "move 1 from 1 to 2", ""].into_iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_run_example() {
+        assert_eq!(top_crates_after(&example(), &CrateMover9000, false), Ok("CMZ".to_string()));
+        assert_eq!(top_crates_after(&example(), &CrateMover9001, false), Ok("MCD".to_string()));
+    }
+
+    #[test]
+    fn test_too_many_crates_names_the_instruction() {
+        let mut lines: Vec<String> = example();
+        lines[7] = "move 4 from 2 to 1".to_string();
+        let err: PuzzleError = top_crates_after(&lines, &CrateMover9000, false).unwrap_err();
+        assert_eq!(err.details(), "Instruction 3 (move 4 from 2 to 1) failed: stack 2 holds only 2 crates");
+        assert_eq!(err.location().line, Some(8));
+    }
+
+    #[test]
+    fn test_failed_instruction_shows_the_line_as_written() {
+        let mut lines: Vec<String> = example();
+        lines[7] = "  move 4 from 2 to 1  ".to_string();
+        let err: PuzzleError = top_crates_after(&lines, &CrateMover9000, false).unwrap_err();
+        assert_eq!(err.location().text.as_deref(), Some("  move 4 from 2 to 1  "));
+    }
+
+    #[test]
+    fn test_missing_stacks_are_errors() {
+        let state: GameState = GameState::parse(example()[..4].to_vec()).unwrap();
+        let validate = |s: &str| s.parse::<Instruction>().unwrap().validate(&state, false).map_err(|e| e.details().to_string());
+        assert_eq!(validate("move 1 from 0 to 1"), Err("there is no stack 0, the stacks are numbered 1 to 3".to_string()));
+        assert_eq!(validate("move 1 from 1 to 4"), Err("there is no stack 4, the stacks are numbered 1 to 3".to_string()));
+        assert_eq!(validate("move 1 from 2 to 2"), Err("stack 2 cannot be moved onto itself".to_string()));
+    }
+
+    #[test]
+    fn test_lenient_moves_what_is_there() {
+        let mut lines: Vec<String> = example();
+        lines[6] = "move 9 from 1 to 3".to_string();
+        assert_eq!(top_crates_after(&lines, &CrateMover9000, true), top_crates_after(&example(), &CrateMover9000, false));
+    }
+
+    #[test]
+    fn test_migrate_too_many_is_an_error() {
+        assert!(Stack{crates: crates("D")}.migrate_sync(Stack{crates: VecDeque::new()}, 2).is_err());
+    }
+
+    #[test]
+    fn test_bad_instruction_line_is_reported() {
+        let mut lines: Vec<String> = example();
+        lines[6] = "move three from 1 to 3".to_string();
+        assert_eq!(divide_stack_instruction(lines).unwrap_err().location().line, Some(7));
+    }
+
+    #[test]
+    fn test_render_parse_round_trip() {
+        let drawing: Vec<String> = example()[..4].to_vec();
+        let state: GameState = GameState::parse(drawing.clone()).unwrap();
+        assert_eq!(state.to_string(), drawing.join("\n"));
+        for seed in 0..20 {
+            let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(seed), 0);
+            let drawing: Vec<String> = lines[..lines.len() - 1].to_vec();
+            let state: GameState = GameState::parse(drawing.clone()).unwrap();
+            assert_eq!(state.to_string(), drawing.join("\n"));
+            assert_eq!(GameState::parse(state.to_string().split('\n').map(String::from).collect()).unwrap(), state);
+        }
+    }
+
+    fn drawing(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_more_than_nine_stacks() {
+        let rows: Vec<String> = drawing(&[
+            "[A]                                         [L]",
+            "[B] [C] [D] [E] [F] [G] [H] [I] [J] [K]     [M]",
+            " 1   2   3   4   5   6   7   8   9  10  11  12 ",
+        ]);
+        let state: GameState = GameState::parse(rows.clone()).unwrap();
+        assert_eq!(state.to_string(), rows.join("\n"));
+        assert_eq!(state.stacks.len(), 12);
+        assert_eq!(state.stacks[9].crates, crates("K"));
+        assert_eq!(state.stacks[10].crates, crates(""));
+        assert_eq!(state.stacks[11].crates, crates("LM"));
+    }
+
+    #[test]
+    fn test_multi_character_labels_round_trip() {
+        let rows: Vec<String> = drawing(&[
+            " [AB]                ",
+            " [C]  [DEF]          ",
+            "  1     2     3     4",
+        ]);
+        let state: GameState = GameState::parse(rows.clone()).unwrap();
+        assert_eq!(state.stacks[0].crates, VecDeque::from(["AB".to_string(), "C".to_string()]));
+        assert_eq!(state.top_crates().concat(), "ABDEF  ");
+        assert_eq!(state.to_string(), rows.join("\n"));
+        assert_eq!(GameState::parse(state.to_string().split('\n').map(String::from).collect()).unwrap(), state);
+    }
+
+    #[test]
+    fn test_wide_crate_falls_back_to_uniform_columns() {
+        let mut state: GameState = GameState::parse(example()[..4].to_vec()).unwrap();
+        state.stacks[0].crates.push_front("ABCDE".to_string());
+        assert_eq!(state.crane_view().last().unwrap(), "   1       2       3   ");
+        assert_eq!(GameState::parse(state.crane_view()).unwrap(), state);
+    }
+
+    #[test]
+    fn test_misaligned_crate_is_an_error() {
+        let error: PuzzleError = GameState::parse(drawing(&["  [A]  ", " 1   2 "])).unwrap_err();
+        assert_eq!(error.location().line, Some(1));
+        assert_eq!(error.location().column, Some(3));
+    }
+
+    #[test]
+    fn test_floating_crate_is_an_error() {
+        let error: PuzzleError = GameState::parse(drawing(&["[A]    ", "    [B]", " 1   2 "])).unwrap_err();
+        assert_eq!(error.location().line, Some(1));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_stacks_must_be_numbered_in_order() {
+        let error: PuzzleError = GameState::parse(drawing(&["[A] [B]", " 1   3 "])).unwrap_err();
+        assert_eq!((error.location().line, error.location().column), (Some(2), Some(6)));
+    }
+
+    #[test]
+    fn test_in_place_matches_copying_run() {
+        let (state, instructions) = divide_stack_instruction(example()).unwrap();
+        let input: Vec<String> = example();
+        for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001, &MaxLift { max_lift: 2 }] {
+            let lines: &[String] = instruction_lines(&input);
+            assert_eq!(run_in_place(state.clone(), &instructions, lines, crane, false), run(&state, &instructions, lines, crane, false));
+        }
+        let too_many: Vec<Instruction> = vec![Instruction { count: 5, source: 1, destination: 2 }];
+        let lines: Vec<String> = vec!["move 5 from 1 to 2".to_string()];
+        assert_eq!(run_in_place(state.clone(), &too_many, &lines, &CrateMover9000, false), run(&state, &too_many, &lines, &CrateMover9000, false));
+    }
+
+    #[test]
+    fn test_crane_variants_agree() {
+        assert_eq!(crate::differential::compare(generate, 200, 0..20, crane_variants()), Ok(20));
+    }
+}
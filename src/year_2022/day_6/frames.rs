@@ -0,0 +1,131 @@
+use itertools::Itertools;
+
+use crate::PuzzleError;
+
+use super::markers::find_markers;
+
+/// How frames are laid out: a marker of `window` different characters followed by `payload` characters.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FrameFormat {
+    pub window: usize,
+    pub payload: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Fault {
+    /// The marker holds this character more than once.
+    RepeatedMarker(char),
+    /// The stream ends before the frame does.
+    Truncated,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    /// Where the marker starts, counting characters from 0.
+    pub offset: usize,
+    pub marker: String,
+    pub payload: String,
+    pub fault: Option<Fault>,
+}
+
+/// Splits `stream` into frames of `format`. The first marker in the stream tells where the frames start,
+/// every following frame is expected right after the previous one and is flagged if its marker is broken.
+/// Markers are found byte by byte, so the stream must be ASCII for offsets to count characters.
+pub fn decode(stream: &str, format: FrameFormat) -> Result<Vec<Frame>, PuzzleError> {
+    if let Some((column, c)) = stream.chars().enumerate().find(|(_, c)| !c.is_ascii()) {
+        return Err(PuzzleError::parse(format!("Expected an ASCII datastream, found {c}")).at_column(column + 1).with_text(stream));
+    }
+    let chars: Vec<char> = stream.chars().collect();
+    let Some(first_end) = find_markers(stream.as_bytes(), &[format.window])?.remove(0).first() else {
+        return Ok(Vec::new());
+    };
+    let frames: Vec<Frame> = (first_end - format.window..chars.len()).step_by(format.window + format.payload).map(|offset| {
+        let marker: String = chars[offset..chars.len().min(offset + format.window)].iter().collect();
+        let payload: String = chars[chars.len().min(offset + format.window)..chars.len().min(offset + format.window + format.payload)].iter().collect();
+        let fault: Option<Fault> = match marker.chars().duplicates().next() {
+            _ if offset + format.window + format.payload > chars.len() => Some(Fault::Truncated),
+            Some(repeated) => Some(Fault::RepeatedMarker(repeated)),
+            None => None,
+        };
+        Frame { offset, marker, payload, fault }
+    }).collect();
+    Ok(frames)
+}
+
+/// Packets carry the messages: the payloads of the intact packets, joined in order, are decoded as
+/// message frames, whose offsets count from the start of the joined payloads.
+pub fn decode_messages(stream: &str, packets: FrameFormat, messages: FrameFormat) -> Result<(Vec<Frame>, Vec<Frame>), PuzzleError> {
+    let packet_frames: Vec<Frame> = decode(stream, packets)?;
+    let carried: String = packet_frames.iter().filter(|frame| frame.fault.is_none()).map(|frame| frame.payload.as_str()).collect();
+    Ok((packet_frames, decode(&carried, messages)?))
+}
+
+#[cfg(test)]
+mod test_frames {
+    use rand::{rngs::StdRng, seq::SliceRandom, RngExt, SeedableRng};
+
+    use super::*;
+    use crate::year_2022::day_6::markers::{MESSAGE_WINDOW, PACKET_WINDOW};
+
+    /// Wraps every payload in a frame with a random marker of different letters.
+    fn encode(rng: &mut StdRng, payloads: &[String], window: usize) -> String {
+        payloads.iter().map(|payload| {
+            let mut marker: Vec<char> = ('a'..='z').collect();
+            marker.shuffle(rng);
+            marker[..window].iter().collect::<String>() + payload
+        }).collect()
+    }
+
+    fn payloads(rng: &mut StdRng, count: usize, length: usize) -> Vec<String> {
+        // a payload of one repeated letter can never be mistaken for a marker
+        (0..count).map(|_| rng.random_range('a'..='z').to_string().repeat(length)).collect()
+    }
+
+    #[test]
+    fn decoded_frames_match_the_encoded_payloads() {
+        let format: FrameFormat = FrameFormat { window: PACKET_WINDOW, payload: 6 };
+        for seed in 0..20 {
+            let mut rng: StdRng = StdRng::seed_from_u64(seed);
+            let payloads: Vec<String> = payloads(&mut rng, 10, format.payload);
+            let frames: Vec<Frame> = decode(&encode(&mut rng, &payloads, format.window), format).unwrap();
+            assert_eq!(frames.iter().map(|frame| frame.payload.clone()).collect::<Vec<String>>(), payloads);
+            assert_eq!(frames.iter().map(|frame| frame.offset).collect::<Vec<usize>>(), (0..10).map(|i| i * 10).collect::<Vec<usize>>());
+            assert!(frames.iter().all(|frame| frame.fault.is_none()));
+        }
+    }
+
+    #[test]
+    fn broken_marker_is_flagged() {
+        let format: FrameFormat = FrameFormat { window: PACKET_WINDOW, payload: 2 };
+        let frames: Vec<Frame> = decode("ccabcdeeabccffabcd", format).unwrap();
+        assert_eq!(frames[0], Frame { offset: 2, marker: "abcd".to_string(), payload: "ee".to_string(), fault: None });
+        assert_eq!(frames[1].fault, Some(Fault::RepeatedMarker('c')));
+        assert_eq!(frames[2].fault, Some(Fault::Truncated));
+    }
+
+    #[test]
+    fn non_ascii_stream_is_rejected() {
+        let error: PuzzleError = decode("cçabcdee", FrameFormat { window: PACKET_WINDOW, payload: 2 }).unwrap_err();
+        assert!(matches!(error, PuzzleError::Parse { .. }));
+        assert_eq!(error.location().column, Some(2));
+    }
+
+    #[test]
+    fn stream_without_marker_has_no_frames() {
+        assert_eq!(decode("aabbaabb", FrameFormat { window: PACKET_WINDOW, payload: 2 }), Ok(vec![]));
+    }
+
+    #[test]
+    fn messages_are_carried_by_intact_packets() {
+        let mut rng: StdRng = StdRng::seed_from_u64(3);
+        let messages: Vec<String> = payloads(&mut rng, 3, 10);
+        let carried: String = encode(&mut rng, &messages, MESSAGE_WINDOW);
+        let packet_payloads: Vec<String> = carried.chars().chunks(8).into_iter().map(|chunk| chunk.collect()).collect();
+        let mut stream: String = encode(&mut rng, &packet_payloads, PACKET_WINDOW);
+        // a broken packet in the middle of the stream is dropped before the messages are decoded
+        stream.insert_str(12, "aaaazzzzzzzz");
+        let (packets, decoded) = decode_messages(&stream, FrameFormat { window: PACKET_WINDOW, payload: 8 }, FrameFormat { window: MESSAGE_WINDOW, payload: 10 }).unwrap();
+        assert_eq!(packets.iter().filter(|packet| packet.fault.is_some()).count(), 1);
+        assert_eq!(decoded.iter().map(|message| message.payload.clone()).collect::<Vec<String>>(), messages);
+    }
+}
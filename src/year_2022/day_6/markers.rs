@@ -0,0 +1,118 @@
+use std::{collections::VecDeque, io::{BufReader, Read}};
+
+use crate::PuzzleError;
+
+pub const PACKET_WINDOW: usize = 4;
+pub const MESSAGE_WINDOW: usize = 14;
+
+/// Every marker of one window size, as the number of characters read when each marker is complete.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Markers {
+    pub size: usize,
+    pub ends: Vec<usize>,
+}
+
+impl Markers {
+    pub fn first(&self) -> Option<usize> {
+        self.ends.first().copied()
+    }
+}
+
+/// How often every byte appears among the last `size` bytes of the stream, and how many bytes appear more than once.
+struct Window {
+    size: usize,
+    counts: [usize; 256],
+    repeated: usize,
+}
+
+impl Window {
+    fn new(size: usize) -> Self {
+        Window { size, counts: [0; 256], repeated: 0 }
+    }
+
+    /// Takes `incoming` into the window and drops `outgoing`, the byte that fell out of it if the
+    /// window was already full. Tells whether every byte in the window is now different.
+    fn slide(&mut self, incoming: u8, outgoing: Option<u8>) -> bool {
+        self.counts[incoming as usize] += 1;
+        if self.counts[incoming as usize] == 2 {
+            self.repeated += 1;
+        }
+        if let Some(outgoing) = outgoing {
+            self.counts[outgoing as usize] -= 1;
+            if self.counts[outgoing as usize] == 1 {
+                self.repeated -= 1;
+            }
+        }
+        self.repeated == 0
+    }
+}
+
+/// Reads `stream` once, keeping a rolling count of the bytes in a window of each size in `sizes`,
+/// and returns every position where the last bytes of a window are all different.
+pub fn find_markers(stream: impl Read, sizes: &[usize]) -> Result<Vec<Markers>, PuzzleError> {
+    if sizes.contains(&0) {
+        return Err(PuzzleError::validation("A marker needs a window of at least one character"));
+    }
+    let longest: usize = sizes.iter().copied().max().unwrap_or(0);
+    // the bytes still in the longest window, and the one just before it that is about to drop out
+    let mut recent: VecDeque<u8> = VecDeque::with_capacity(longest + 2);
+    let mut windows: Vec<Window> = sizes.iter().map(|size| Window::new(*size)).collect();
+    let mut markers: Vec<Markers> = sizes.iter().map(|size| Markers { size: *size, ends: Vec::new() }).collect();
+    for (read, byte) in BufReader::new(stream).bytes().enumerate() {
+        let byte: u8 = byte.map_err(|e| PuzzleError::execution(format!("Could not read the datastream: {e}")))?;
+        recent.push_back(byte);
+        if recent.len() > longest + 1 {
+            recent.pop_front();
+        }
+        for (window, markers) in windows.iter_mut().zip(markers.iter_mut()) {
+            let outgoing: Option<u8> = recent.len().checked_sub(window.size + 1).map(|i| recent[i]);
+            if window.slide(byte, outgoing) && read + 1 >= window.size {
+                markers.ends.push(read + 1);
+            }
+        }
+    }
+    Ok(markers)
+}
+
+#[cfg(test)]
+mod test_markers {
+    use itertools::Itertools;
+
+    use super::*;
+
+    const EXAMPLES: [(&str, usize, usize); 5] = [
+        ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+        ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+        ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+        ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+        ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+    ];
+
+    #[test]
+    fn first_markers_of_the_examples() {
+        for (stream, packet, message) in EXAMPLES {
+            let markers: Vec<Markers> = find_markers(stream.as_bytes(), &[PACKET_WINDOW, MESSAGE_WINDOW]).unwrap();
+            assert_eq!((markers[0].first(), markers[1].first()), (Some(packet), Some(message)));
+        }
+    }
+
+    #[test]
+    fn every_marker_matches_checking_each_window() {
+        for (stream, _, _) in EXAMPLES {
+            for size in 1..=15 {
+                let expected: Vec<usize> = stream.as_bytes().windows(size).positions(|window| window.iter().all_unique()).map(|i| i + size).collect();
+                assert_eq!(find_markers(stream.as_bytes(), &[size]).unwrap()[0].ends, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn short_stream_has_no_markers() {
+        assert_eq!(find_markers("abc".as_bytes(), &[PACKET_WINDOW]).unwrap()[0].first(), None);
+    }
+
+    #[test]
+    fn empty_window_is_rejected() {
+        assert!(find_markers("abcd".as_bytes(), &[0]).is_err());
+    }
+}
@@ -1 +1,3 @@
+pub mod frames;
+pub mod markers;
 pub mod tuning_trouble;
@@ -1,35 +1,86 @@
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::SliceRandom, RngExt};
 
-fn first_unique_window_index(source: &String, window_size: usize) -> Option<usize> {
-    source
-        .chars()
-        .collect::<Vec<char>>()
-        .windows(window_size)
-        .position(|x| x.into_iter().all_unique())
+use crate::{cli::Args, PuzzleError};
+
+use super::{frames::{decode_messages, Fault, Frame, FrameFormat}, markers::{find_markers, Markers, MESSAGE_WINDOW, PACKET_WINDOW}};
+
+/// Generates a single datastream of at least `size` characters with a start-of-message marker
+/// planted at a random position.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let mut stream: Vec<char> = (0..size.max(MESSAGE_WINDOW)).map(|_| rng.random_range('a'..='z')).collect();
+    let mut marker: Vec<char> = ('a'..='z').collect();
+    marker.shuffle(rng);
+    let start: usize = rng.random_range(0..=stream.len() - MESSAGE_WINDOW);
+    stream[start..start + MESSAGE_WINDOW].copy_from_slice(&marker[..MESSAGE_WINDOW]);
+    vec![stream.into_iter().collect()]
+}
+
+fn print_markers(lines: &[String], packet_window: usize, message_window: usize, all: bool) -> Result<(), PuzzleError> {
+    // a file ending in a newline leaves an empty last line
+    for line in lines.iter().filter(|line| !line.is_empty()) {
+        let found: Vec<Markers> = find_markers(line.as_bytes(), &[packet_window, message_window])?;
+        for (kind, markers) in ["packet", "message"].into_iter().zip(found) {
+            match markers.first() {
+                Some(_) if all => println!("{} {kind} markers of {} characters, ending after characters {}", markers.ends.len(), markers.size, markers.ends.iter().join(", ")),
+                Some(end) => println!("{kind} marker starts after character {end}"),
+                None => println!("Could not find a {kind} marker"),
+            }
+        }
+    }
+    Ok(())
 }
 
-fn message_marker_index(stream: &String) -> Option<usize> {
-    let stream = &stream[5..].to_owned();
-    first_unique_window_index(stream, 14)
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    print_markers(&lines, PACKET_WINDOW, MESSAGE_WINDOW, false)
 }
 
-fn packet_marker_index(stream: &String) -> Option<usize> {
-    first_unique_window_index(stream, 4)
+fn describe(kind: &str, frame: &Frame) -> String {
+    let status: String = match &frame.fault {
+        None => String::new(),
+        Some(Fault::RepeatedMarker(c)) => format!(" (corrupted: marker repeats {c})"),
+        Some(Fault::Truncated) => " (corrupted: truncated)".to_string(),
+    };
+    format!("{kind} at {} [{}] {}{status}", frame.offset, frame.marker, frame.payload)
 }
 
-pub fn solve(lines: Vec<String>) {
-    for line in lines {
-        let start_mark_i = packet_marker_index(&line);
-        match start_mark_i {
-            Some(i) => {
-                println!("packet marker starts after character {}", i + 4);
-                let start_mark_i = message_marker_index(&line);
-                match start_mark_i {
-                    Some(i) => println!("message marker starts after character {}", i + 19),
-                    None => println!("Could not find a marker"),
-                }
-            }
-            None => println!("Could not find a marker"),
-        };
+fn print_frames(lines: &[String], packets: FrameFormat, messages: FrameFormat) -> Result<(), PuzzleError> {
+    for (i, line) in lines.iter().enumerate().filter(|(_, line)| !line.is_empty()) {
+        let (packet_frames, message_frames) = decode_messages(line, packets, messages).map_err(|e| e.at_line(i + 1))?;
+        packet_frames.iter().for_each(|frame| println!("{}", describe("packet", frame)));
+        message_frames.iter().for_each(|frame| println!("{}", describe("message", frame)));
+    }
+    Ok(())
+}
+
+/// Looks for start-of-packet markers of `--packet-window` characters and start-of-message markers of
+/// `--message-window` characters, 4 and 14 by default. With `--all` every marker is listed, not just the first.
+/// With `--frames` the stream is decoded into packets of `--packet-payload` characters, carrying messages
+/// of `--message-payload` characters.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    let packet_window: usize = args.get("packet-window")?.unwrap_or(PACKET_WINDOW);
+    let message_window: usize = args.get("message-window")?.unwrap_or(MESSAGE_WINDOW);
+    if args.has("frames") {
+        let packets: FrameFormat = FrameFormat { window: packet_window, payload: args.require("packet-payload")? };
+        let messages: FrameFormat = FrameFormat { window: message_window, payload: args.require("message-payload")? };
+        return print_frames(&lines, packets, messages);
+    }
+    print_markers(&lines, packet_window, message_window, args.has("all"))
+}
+
+#[cfg(test)]
+mod test_tuning_trouble {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn generated_stream_has_markers() {
+        for seed in 0..20 {
+            let stream: String = generate(&mut StdRng::seed_from_u64(seed), 200).remove(0);
+            assert_eq!(stream.len(), 200);
+            let markers: Vec<Markers> = find_markers(stream.as_bytes(), &[PACKET_WINDOW, MESSAGE_WINDOW]).unwrap();
+            assert!(markers.iter().all(|markers| markers.first().is_some()));
+        }
     }
 }
@@ -1,43 +1,56 @@
-use aho_corasick::AhoCorasick;
+use std::{fmt::Display, path::Path};
 
-trait DigitCapture {
-    fn capture_digits(line: String) -> Option<(u8, u8)>;
+use itertools::Itertools;
+
+use rand::{rngs::StdRng, seq::{IndexedRandom, SliceRandom}, RngExt};
+
+use crate::{cli::Args, PuzzleError};
+
+use super::lexicon::Lexicon;
+
+/// A digit or a word for digits found in a line, spanning the bytes `start..end`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Token {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+    pub digits: Vec<u8>,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" at {}..{}", self.text, self.start, self.end)
+    }
+}
+
+pub trait DigitCapture {
+    fn capture_digits(&self, line: &str) -> Option<(u8, u8)>;
+
+    /// Every token in the line, in the order the first and last digits are picked from.
+    fn tokens(&self, line: &str) -> Vec<Token>;
 }
 
 struct JustDigits;
 impl DigitCapture for JustDigits {
-    fn capture_digits(line: String) -> Option<(u8, u8)> {
+    fn capture_digits(&self, line: &str) -> Option<(u8, u8)> {
         let digits: Vec<u32> = line.chars().filter_map(|c: char| c.to_digit(10)).collect();
         match (digits.first(), digits.last()) {
             (Some(x), Some(y)) => Some((*x as u8, *y as u8)),
             _ => None,
         }
     }
-}
 
-struct DigitNames;
-impl DigitCapture for DigitNames {
-    fn capture_digits(line: String) -> Option<(u8, u8)> {
-        let re_digit: &Vec<&str> = &vec!["1", "2", "3", "4", "5", "6", "7", "8", "9", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
-        let digit: &Vec<u8> = &vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 1, 2, 3, 4, 5, 6, 7, 8, 9];
-        let ac: AhoCorasick = AhoCorasick::builder().ascii_case_insensitive(true).build(re_digit).unwrap();
-        let digits: Vec<u8> = ac.find_overlapping_iter(&line).map(|mat| (digit[mat.pattern().as_usize()])).collect();
-        match (digits.first(), digits.last()) {
-            (Some(x), Some(y)) => Some((*x, *y)),
-            _ => None,
-        }
-    } 
+    fn tokens(&self, line: &str) -> Vec<Token> {
+        line.char_indices().filter_map(|(i, c)| c.to_digit(10).map(|digit| Token { start: i, end: i + 1, text: c.to_string(), digits: vec![digit as u8] })).collect()
+    }
 }
 
 fn concat_digits(digits: (u8, u8)) -> u32 {
     digits.0 as u32 * 10 + digits.1 as u32
 }
 
-fn collect_calibration_values<DC: DigitCapture>(line: String, _digit_capturer: &DC) -> Option<u32> {
-    match DC::capture_digits(line) {
-        Some((x, y)) => Some(concat_digits((x, y))),
-        None => None,
-    }
+fn collect_calibration_values<DC: DigitCapture>(line: String, digit_capturer: &DC) -> Option<u32> {
+    digit_capturer.capture_digits(&line).map(concat_digits)
 }
 
 fn total_calibration_value<DC: DigitCapture>(lines: Vec<String>, digit_capturer: DC) -> u32 {
@@ -45,15 +58,111 @@ fn total_calibration_value<DC: DigitCapture>(lines: Vec<String>, digit_capturer:
     cal_values.into_iter().sum()
 }
 
-pub fn solve(lines: Vec<String>) {
+/// The tokens a line's calibration value was read from, with the tokens that share bytes.
+#[derive(Debug, PartialEq, Eq)]
+struct LineReport {
+    line: usize,
+    first: Token,
+    last: Token,
+    overlaps: Vec<(Token, Token)>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct CalibrationReport {
+    lines: Vec<LineReport>,
+    no_digits: Vec<usize>,
+    total: u32,
+}
+
+fn calibration_report<DC: DigitCapture>(lines: &[String], digit_capturer: &DC) -> CalibrationReport {
+    let mut report: CalibrationReport = CalibrationReport { lines: Vec::new(), no_digits: Vec::new(), total: 0 };
+    // a file ending in a newline leaves an empty last line
+    let end: usize = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1);
+    for (i, line) in lines[..end].iter().enumerate() {
+        let tokens: Vec<Token> = digit_capturer.tokens(line);
+        let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+            report.no_digits.push(i + 1);
+            continue;
+        };
+        report.total += concat_digits((first.digits[0], *last.digits.last().unwrap()));
+        let overlaps: Vec<(Token, Token)> = tokens.iter().tuple_combinations()
+            .filter(|(a, b): &(&Token, &Token)| a.start < b.end && b.start < a.end)
+            .map(|(a, b)| (a.clone(), b.clone()))
+            .collect();
+        report.lines.push(LineReport { line: i + 1, first: first.clone(), last: last.clone(), overlaps });
+    }
+    report
+}
+
+impl Display for CalibrationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in self.lines.iter() {
+            let value: u32 = concat_digits((line.first.digits[0], *line.last.digits.last().unwrap()));
+            write!(f, "line {}: {value}, first {}, last {}", line.line, line.first, line.last)?;
+            for (a, b) in line.overlaps.iter() {
+                write!(f, ", {} overlaps {}", a, b)?;
+            }
+            writeln!(f)?;
+        }
+        match self.no_digits[..] {
+            [] => writeln!(f, "Every line has a digit")?,
+            ref lines => writeln!(f, "No digits on lines {}", lines.iter().join(", "))?,
+        }
+        write!(f, "Total: {}", self.total)
+    }
+}
+
+/// Generates `size` lines of filler letters, digits and spelled out digits. Every line holds at
+/// least one plain digit so that both parts have a calibration value for it.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    const DIGIT_NAMES: [&str; 9] = ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+    (0..size).map(|_| {
+        let mut tokens: Vec<String> = vec![rng.random_range(1..=9).to_string()];
+        for _ in 0..rng.random_range(0..8) {
+            tokens.push(match rng.random_range(0..3) {
+                0 => rng.random_range(1..=9).to_string(),
+                1 => DIGIT_NAMES.choose(rng).unwrap().to_string(),
+                _ => (0..rng.random_range(1..=4)).map(|_| rng.random_range('a'..='z')).collect(),
+            });
+        }
+        tokens.shuffle(rng);
+        tokens.concat()
+    }).collect()
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
     let total_val: u32 = total_calibration_value(lines.clone(), JustDigits);
     println!("Final Calibration value for part 1: {total_val}");
-    let total_val: u32 = total_calibration_value(lines.clone(), DigitNames);
+    let total_val: u32 = total_calibration_value(lines.clone(), Lexicon::english());
     println!("Final Calibration value for part 2: {total_val}");
+    Ok(())
+}
+
+/// Reads the spelled out digits of part two in the language picked with `--lexicon` (english, french,
+/// german or spanish) or from the `word=digits` lines of the file given with `--lexicon-file`. With
+/// `--report` every line of part two is listed with the tokens its calibration value was read from.
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    let lexicon: Lexicon = match (args.get::<String>("lexicon-file")?, args.get::<String>("lexicon")?) {
+        (Some(path), _) => Lexicon::load(Path::new(&path))?,
+        (None, Some(language)) => Lexicon::language(&language)?,
+        (None, None) if args.has("report") => Lexicon::english(),
+        (None, None) => return solve(lines),
+    };
+    if args.has("report") {
+        println!("{}", calibration_report(&lines, &lexicon));
+        return Ok(());
+    }
+    let total_val: u32 = total_calibration_value(lines.clone(), JustDigits);
+    println!("Final Calibration value for part 1: {total_val}");
+    let total_val: u32 = total_calibration_value(lines, lexicon);
+    println!("Final Calibration value for part 2: {total_val}");
+    Ok(())
 }
 
 #[cfg(test)]
 mod artistic_calibration {
+    use rand::SeedableRng;
+
     use crate::year_2023::day_1::artistic_calibration::*;
 
     #[test]
@@ -91,21 +200,57 @@ mod artistic_calibration {
 
     #[test]
     fn test_update_digit_names() {
-        assert_eq!(collect_calibration_values("62jfjdsklvnqthree8".to_string(), &DigitNames), Some(68));
+        assert_eq!(collect_calibration_values("62jfjdsklvnqthree8".to_string(), &Lexicon::english()), Some(68));
     }
 
     #[test]
     fn test_update_digit_names_only_digit_str() {
-        assert_eq!(collect_calibration_values("eight".to_string(), &DigitNames), Some(88));
+        assert_eq!(collect_calibration_values("eight".to_string(), &Lexicon::english()), Some(88));
     }
 
     #[test]
     fn test_update_digit_names_consec_digit_str() {
-        assert_eq!(collect_calibration_values("eightninetwothree".to_string(), &DigitNames), Some(83));
+        assert_eq!(collect_calibration_values("eightninetwothree".to_string(), &Lexicon::english()), Some(83));
     }
 
     #[test]
     fn test_update_digit_names_blended_digit_str() {
-        assert_eq!(collect_calibration_values("five72sevenjf59nineeight".to_string(), &DigitNames), Some(58));
+        assert_eq!(collect_calibration_values("five72sevenjf59nineeight".to_string(), &Lexicon::english()), Some(58));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generated_lines_have_digits() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(5), 50);
+        assert!(lines.iter().all(|line| JustDigits.capture_digits(line).is_some()));
+        assert!(lines.iter().all(|line| Lexicon::english().capture_digits(line).is_some()));
+    }
+
+    #[test]
+    fn test_report_picks_first_and_last_tokens() {
+        let lines: Vec<String> = ["xtwone3four", "abc", "eightwo", ""].map(String::from).to_vec();
+        let report: CalibrationReport = calibration_report(&lines, &Lexicon::english());
+        assert_eq!(report.total, 24 + 82);
+        assert_eq!(report.no_digits, vec![2]);
+        assert_eq!(report.lines[0].first, Token { start: 1, end: 4, text: "two".to_string(), digits: vec![2] });
+        assert_eq!((report.lines[0].last.start, report.lines[0].last.end), (7, 11));
+        assert_eq!(report.lines[1].overlaps.len(), 1);
+        assert_eq!(report.lines[1].overlaps[0].1.text, "two");
+    }
+
+    #[test]
+    fn test_report_matches_the_total() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(6), 50);
+        assert_eq!(calibration_report(&lines, &Lexicon::english()).total, total_calibration_value(lines.clone(), Lexicon::english()));
+        assert_eq!(calibration_report(&lines, &JustDigits).total, total_calibration_value(lines, JustDigits));
+    }
+
+    #[test]
+    fn test_report_display() {
+        let report: CalibrationReport = calibration_report(&["twone".to_string(), "x".to_string()], &Lexicon::english());
+        assert_eq!(report.to_string(), [
+            "line 1: 21, first \"two\" at 0..3, last \"one\" at 2..5, \"two\" at 0..3 overlaps \"one\" at 2..5",
+            "No digits on lines 2",
+            "Total: 21",
+        ].join("\n"));
+    }
+}
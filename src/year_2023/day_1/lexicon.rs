@@ -0,0 +1,128 @@
+use std::{fs, path::Path, str::FromStr};
+
+use aho_corasick::AhoCorasick;
+
+use crate::PuzzleError;
+
+use super::artistic_calibration::{DigitCapture, Token};
+
+/// The spelled out digits one to nine of each built-in language.
+const LANGUAGES: [(&str, [&str; 9]); 4] = [
+    ("english", ["one", "two", "three", "four", "five", "six", "seven", "eight", "nine"]),
+    ("french", ["un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf"]),
+    ("german", ["eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun"]),
+    ("spanish", ["uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve"]),
+];
+
+/// Words standing for digits, matched with the plain digits 1 to 9 in a single automaton. A word may
+/// stand for several digits, e.g. `twelve=12`, and a word for 0 makes the plain digit 0 count too.
+pub struct Lexicon {
+    automaton: AhoCorasick,
+    /// the digits of each pattern of the automaton, by pattern index
+    values: Vec<Vec<u8>>,
+}
+
+impl Lexicon {
+    pub fn new(words: &[(String, Vec<u8>)]) -> Result<Self, PuzzleError> {
+        let zero: bool = words.iter().any(|(_, digits)| digits.contains(&0));
+        let plain_digits = (if zero { 0 } else { 1 }..=9).map(|digit: u8| (digit.to_string(), vec![digit]));
+        let (patterns, values): (Vec<String>, Vec<Vec<u8>>) = plain_digits.chain(words.iter().cloned()).unzip();
+        let automaton: AhoCorasick = AhoCorasick::builder().ascii_case_insensitive(true).build(&patterns)
+            .map_err(|e| PuzzleError::validation(format!("Could not build the lexicon: {e}")))?;
+        Ok(Lexicon { automaton, values })
+    }
+
+    /// One of the built-in languages: english, french, german or spanish.
+    pub fn language(name: &str) -> Result<Self, PuzzleError> {
+        let (_, words) = LANGUAGES.iter().find(|(language, _)| *language == name.to_lowercase())
+            .ok_or(PuzzleError::validation(format!("Unknown language {name}, expected one of {}", LANGUAGES.map(|(language, _)| language).join(", "))))?;
+        Lexicon::new(&words.iter().zip(1..).map(|(word, digit)| (word.to_string(), vec![digit])).collect::<Vec<(String, Vec<u8>)>>())
+    }
+
+    pub fn english() -> Self {
+        Lexicon::language("english").expect("english is a built-in language")
+    }
+
+    pub fn load(path: &Path) -> Result<Self, PuzzleError> {
+        let contents: String = fs::read_to_string(path).map_err(|e| PuzzleError::execution(format!("Could not read the lexicon {}: {e}", path.display())))?;
+        contents.parse::<Lexicon>().map_err(|e| e.with_path(path.display().to_string()))
+    }
+}
+
+/// One `word=digits` pair per line, e.g. `zero=0` or `twelve=12`. Blank lines and lines starting with `#` are skipped.
+impl FromStr for Lexicon {
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words: Vec<(String, Vec<u8>)> = Vec::new();
+        for (i, line) in s.lines().map(str::trim).enumerate().filter(|(_, line)| !line.is_empty() && !line.starts_with('#')) {
+            let tagged = |e: PuzzleError| e.at_line(i + 1).with_text(line);
+            let (word, digits) = line.split_once('=').map(|(word, digits)| (word.trim(), digits.trim()))
+                .ok_or(tagged(PuzzleError::parse("Expected a word and its digits, such as one=1")))?;
+            if word.is_empty() || digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(tagged(PuzzleError::parse(format!("Expected a word and its digits, such as one=1, found {line}"))));
+            }
+            if words.iter().any(|(seen, _)| seen.eq_ignore_ascii_case(word)) {
+                return Err(tagged(PuzzleError::validation(format!("The word {word} is listed twice"))));
+            }
+            words.push((word.to_string(), digits.bytes().map(|digit| digit - b'0').collect()));
+        }
+        Lexicon::new(&words)
+    }
+}
+
+impl DigitCapture for Lexicon {
+    /// The first digit of the first word and the last digit of the last word, where overlapping words
+    /// such as "twone" both count and the last word is the one that ends last.
+    fn capture_digits(&self, line: &str) -> Option<(u8, u8)> {
+        let mut matches = self.automaton.find_overlapping_iter(line).map(|mat| &self.values[mat.pattern().as_usize()]);
+        let first: &Vec<u8> = matches.next()?;
+        let last: &Vec<u8> = matches.last().unwrap_or(first);
+        Some((first[0], *last.last()?))
+    }
+
+    fn tokens(&self, line: &str) -> Vec<Token> {
+        self.automaton.find_overlapping_iter(line).map(|mat| Token {
+            start: mat.start(),
+            end: mat.end(),
+            text: line[mat.range()].to_string(),
+            digits: self.values[mat.pattern().as_usize()].clone(),
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test_lexicon {
+    use super::*;
+
+    #[test]
+    fn languages_name_the_digits() {
+        assert_eq!(Lexicon::language("french").unwrap().capture_digits("xquatreoneseptx"), Some((4, 7)));
+        assert_eq!(Lexicon::language("German").unwrap().capture_digits("fünfzig3"), Some((5, 3)));
+        assert_eq!(Lexicon::language("spanish").unwrap().capture_digits("cinco y nueve"), Some((5, 9)));
+        assert!(Lexicon::language("klingon").is_err());
+    }
+
+    #[test]
+    fn overlapping_words_both_count() {
+        assert_eq!(Lexicon::english().capture_digits("xtwone"), Some((2, 1)));
+        assert_eq!(Lexicon::english().capture_digits("eightwo"), Some((8, 2)));
+    }
+
+    #[test]
+    fn zero_and_multi_digit_words() {
+        let lexicon: Lexicon = "# teens\nzero = 0\ntwelve=12\n\nseventeen=17".parse().unwrap();
+        assert_eq!(lexicon.capture_digits("zeroabc"), Some((0, 0)));
+        assert_eq!(lexicon.capture_digits("twelve"), Some((1, 2)));
+        assert_eq!(lexicon.capture_digits("0 and seventeen"), Some((0, 7)));
+        // without a word for zero, a plain 0 is not a digit, as in the puzzle
+        assert_eq!(Lexicon::english().capture_digits("0"), None);
+    }
+
+    #[test]
+    fn bad_lexicon_lines_are_reported() {
+        assert_eq!("one=1\ntwo".parse::<Lexicon>().err().unwrap().location().line, Some(2));
+        assert_eq!("one=1\n# x\nten=1O".parse::<Lexicon>().err().unwrap().location().line, Some(3));
+        assert!("one=1\nONE=1".parse::<Lexicon>().is_err());
+    }
+}
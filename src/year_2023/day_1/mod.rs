@@ -1 +1,2 @@
-pub mod artistic_calibration;
\ No newline at end of file
+pub mod artistic_calibration;
+pub mod lexicon;
@@ -1,34 +1,20 @@
-use std::{collections::HashMap, error::Error, fmt::Display};
+use std::collections::{HashMap, HashSet};
 
-use crate::ParseInputError;
+use crate::{render::{Canvas, CellStyle, Colour}, PuzzleError};
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::IndexedRandom, RngExt};
 use petgraph::{graph, graphmap, prelude};
 use geo::{Point, LineString, Polygon, Contains};
 
 
-#[derive(Debug)]
-struct NoCyclesInGraph;
-
-impl Display for NoCyclesInGraph {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "No cycles found in graph")
-    }
-}
-
-impl Error for NoCyclesInGraph {
-    fn description(&self) -> &str {
-        "No cycles found in graph"
-    }
-}
-
 type Position = (usize, usize);
 
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 enum Pipe { Start, Vertical, Horizontal, NorthEast, NorthWest, SouthEast, SouthWest, Ground }
 impl TryFrom<char> for Pipe {
-    type Error = ParseInputError;
+    type Error = PuzzleError;
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
@@ -40,7 +26,7 @@ impl TryFrom<char> for Pipe {
             'F' => Ok(Self::SouthEast),
             '7' => Ok(Self::SouthWest),
             '.' => Ok(Self::Ground),
-            _ => Err(Self::Error {details: format!("Could not parse {value} to a pipe type")}),
+            _ => Err(Self::Error::parse(format!("Could not parse {value} to a pipe type"))),
         }
     }
 }
@@ -52,7 +38,7 @@ struct Tile {
 }
 
 impl Tile {
-    fn new(pos: Position, pipe_letter: char) -> Result<Self, ParseInputError> {
+    fn new(pos: Position, pipe_letter: char) -> Result<Self, PuzzleError> {
         Ok(Tile {pos, pipe: Pipe::try_from(pipe_letter)?})
     }
 
@@ -84,14 +70,14 @@ impl Tile {
 struct PipeMaze(graphmap::DiGraphMap<Tile, ()>);
 
 impl TryFrom<Vec<String>> for PipeMaze {
-    type Error = ParseInputError;
+    type Error = PuzzleError;
 
     fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
         let nodes: Vec<Tile> = value.into_iter().enumerate().map(|(row_n, row): (usize, String)|
             row.chars().enumerate().map(|(column_n, pipe_letter): (usize, char)|
-                Tile::new((row_n, column_n), pipe_letter)
+                Tile::new((row_n, column_n), pipe_letter).map_err(|e| e.at_line(row_n + 1).at_column(column_n + 1).with_text(row.as_str()))
             ).collect()
-        ).collect::<Result<Vec<Vec<Tile>>, ParseInputError>>()?.into_iter().flatten().collect();
+        ).collect::<Result<Vec<Vec<Tile>>, PuzzleError>>()?.into_iter().flatten().collect();
         Ok(Self::from(nodes))
     }
 }
@@ -156,9 +142,9 @@ impl PipeMaze {
         row_range.cartesian_product(col_range)
     }
 
-    fn polygon(&self) -> Result<Polygon, NoCyclesInGraph> {
+    fn polygon(&self) -> Result<Polygon, PuzzleError> {
         match self.maze_is_cycle() {
-            false => Err(NoCyclesInGraph),
+            false => Err(PuzzleError::validation("No cycles found in graph")),
             true => Ok(Polygon::new(
                 LineString::from(self.0.nodes().map(|n: Tile| (n.pos.0 as f64, n.pos.1 as f64) as (f64, f64)).collect::<Vec<(f64, f64)>>()),
                 vec![]
@@ -166,26 +152,110 @@ impl PipeMaze {
         }
     }
 
-    pub fn interior_positions(&self) -> Option<impl Iterator<Item=Position>> {
-        match self.polygon() {
-            Err(_) => None,
-            Ok(polygon) => Some(self.grid_positions().filter(move |pos: &Position| polygon.contains(&Point::new(pos.0 as f64, pos.1 as f64))).into_iter())
-        }
+    pub fn interior_positions(&self) -> Result<impl Iterator<Item=Position>, PuzzleError> {
+        let polygon: Polygon = self.polygon()?;
+        Ok(self.grid_positions().filter(move |pos: &Position| polygon.contains(&Point::new(pos.0 as f64, pos.1 as f64))))
+    }
+}
 
+/// Corners of the outline of a random shape made of `columns` vertical spans of cells, walked
+/// clockwise one unit at a time. Neighbouring spans always overlap, so the outline never touches itself.
+fn random_outline(rng: &mut StdRng, rows: usize, columns: usize) -> Vec<(usize, usize)> {
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    while spans.len() < columns {
+        let (a, b): (usize, usize) = (rng.random_range(0..rows), rng.random_range(0..rows));
+        let (top, bottom) = (a.min(b), a.max(b));
+        if spans.last().is_none_or(|&(previous_top, previous_bottom)| top <= previous_bottom && bottom >= previous_top) {
+            spans.push((top, bottom));
+        }
+    }
+    let mut outline: Vec<(usize, usize)> = vec![(spans[0].0, 0)];
+    let mut walk_to = |target: (usize, usize)| {
+        while let Some(&(row, column)) = outline.last().filter(|&&corner| corner != target) {
+            outline.push((
+                if row < target.0 { row + 1 } else if row > target.0 { row - 1 } else { row },
+                if column < target.1 { column + 1 } else if column > target.1 { column - 1 } else { column },
+            ));
+        }
+    };
+    for (column, (top, _)) in spans.iter().enumerate() {
+        walk_to((*top, column));
+        walk_to((*top, column + 1));
+    }
+    for (column, (_, bottom)) in spans.iter().enumerate().rev() {
+        walk_to((bottom + 1, column + 1));
+        walk_to((bottom + 1, column));
     }
+    walk_to((spans[0].0, 0));
+    outline.pop();
+    outline
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok(grid) = PipeMaze::try_from(lines) {
-        let cycle: Vec<Tile> = grid.cycle_from_start();
-        println!("{}", cycle.len() / 2 as usize);
-        println!("{}", PipeMaze::from(cycle).interior_positions().unwrap().collect::<Vec<_>>().len());
+/// Generates a roughly `size` by `size` field holding one closed loop through `S`, with every
+/// other tile filled with ground or stray pipes. Stray pipes never sit next to `S`, so the loop
+/// is the only cycle through the start.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let cells: usize = (size / 2).max(1);
+    let outline: Vec<(usize, usize)> = random_outline(rng, cells, cells);
+    // doubling the corners leaves room for the pipe between each pair, and one tile of margin on every side
+    let cycle: Vec<Position> = outline.iter().zip(outline.iter().cycle().skip(1))
+        .flat_map(|(a, b)| [(2 * a.0 + 1, 2 * a.1 + 1), (a.0 + b.0 + 1, a.1 + b.1 + 1)])
+        .collect();
+    let mut grid: Vec<Vec<char>> = (0..2 * cells + 3).map(|_| (0..2 * cells + 3).map(|_| {
+        if rng.random_bool(0.5) { '.' } else { *['|', '-', 'L', 'J', 'F', '7'].choose(rng).unwrap() }
+    }).collect()).collect();
+    for (i, pos) in cycle.iter().enumerate() {
+        let (previous, next) = (cycle[(i + cycle.len() - 1) % cycle.len()], cycle[(i + 1) % cycle.len()]);
+        let connects = |direction: (isize, isize)| [previous, next].iter().any(|other| {
+            (other.0 as isize - pos.0 as isize, other.1 as isize - pos.1 as isize) == direction
+        });
+        grid[pos.0][pos.1] = match (connects((-1, 0)), connects((1, 0)), connects((0, 1))) {
+            (true, true, _) => '|',
+            (true, false, true) => 'L',
+            (true, false, false) => 'J',
+            (false, true, true) => 'F',
+            (false, true, false) => '7',
+            (false, false, _) => '-',
+        };
+    }
+    let start: Position = *cycle.choose(rng).unwrap();
+    grid[start.0][start.1] = 'S';
+    for neighbour in [(start.0 - 1, start.1), (start.0 + 1, start.1), (start.0, start.1 - 1), (start.0, start.1 + 1)] {
+        if !cycle.contains(&neighbour) {
+            grid[neighbour.0][neighbour.1] = '.';
+        }
     }
+    grid.into_iter().map(|row| row.into_iter().collect()).collect()
+}
+
+/// Colours the loop through the start orange, the tiles it encloses green and all other tiles blue.
+pub fn render(lines: Vec<String>) -> Result<Vec<(String, Canvas)>, PuzzleError> {
+    const LOOP: Colour = Colour(240, 140, 50);
+    const INSIDE: Colour = Colour(130, 200, 120);
+    const OUTSIDE: Colour = Colour(205, 215, 235);
+    let cycle: Vec<Tile> = PipeMaze::try_from(lines.clone())?.cycle_from_start();
+    let loop_positions: HashSet<Position> = cycle.iter().map(|tile| tile.pos).collect();
+    let inside: HashSet<Position> = PipeMaze::from(cycle).interior_positions()?.collect();
+    let canvas: Canvas = Canvas::new(&lines, |pos, c| {
+        let fill: Colour = if loop_positions.contains(&pos) { LOOP } else if inside.contains(&pos) { INSIDE } else { OUTSIDE };
+        CellStyle::new(fill, c)
+    });
+    Ok(vec![(String::new(), canvas)])
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let grid: PipeMaze = PipeMaze::try_from(lines)?;
+    let cycle: Vec<Tile> = grid.cycle_from_start();
+    println!("{}", cycle.len() / 2);
+    println!("{}", PipeMaze::from(cycle).interior_positions()?.collect::<Vec<_>>().len());
+    Ok(())
 }
 
 
 #[cfg(test)]
 mod test_pipe_maze {
+    use rand::SeedableRng;
+
     use crate::year_2023::day_10::pipe_maze::*;
 
     const GRID: [&str; 5] = ["..F7.", ".FJ|.", "SJ.L7", "|F--J", "LJ..."];
@@ -208,7 +278,7 @@ mod test_pipe_maze {
 
     #[test]
     fn parse_pipe_types_with_invalid_char() {
-        assert_eq!(Pipe::try_from('X'), Err(ParseInputError {details: "Could not parse X to a pipe type".to_string()}));
+        assert_eq!(Pipe::try_from('X'), Err(PuzzleError::parse("Could not parse X to a pipe type")));
     }
 
     #[test]
@@ -240,6 +310,13 @@ mod test_pipe_maze {
         assert!(PipeMaze::try_from(vec!["..F7.".to_string(), ".FX|.".to_string(), "SJ.L7".to_string()]).is_err());
     }
 
+    #[test]
+    fn parse_grid_reports_position_of_bad_tile() {
+        let err = PipeMaze::try_from(vec!["..F7.".to_string(), ".FX|.".to_string()]).unwrap_err();
+        assert_eq!(err.location().line, Some(2));
+        assert_eq!(err.location().column, Some(3));
+    }
+
     #[test]
     fn get_indicies() {
         let grid: PipeMaze = PipeMaze::try_from(grid()).unwrap();
@@ -290,4 +367,29 @@ mod test_pipe_maze {
             (0..=6).cartesian_product(0..=8).collect::<Vec<Position>>()
         );
     }
+
+    #[test]
+    fn generated_loop_is_the_cycle_through_start() {
+        for seed in 0..10 {
+            let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(seed), 20);
+            // the outline is the first thing drawn from the generator, so the same seed reproduces it
+            let outline: Vec<(usize, usize)> = random_outline(&mut StdRng::seed_from_u64(seed), 10, 10);
+            assert_eq!(PipeMaze::try_from(lines).unwrap().cycle_from_start().len(), 2 * outline.len());
+        }
+    }
+
+    #[test]
+    fn render_colours_loop_and_inside() {
+        let lines: Vec<String> = ["...........", ".S-------7.", ".|F-----7|.", ".||.....||.", ".||.....||.", ".|L-7.F-J|.", ".|..|.|..|.", ".L--J.L--J.", "..........."].map(String::from).to_vec();
+        let pictures: Vec<(String, Canvas)> = render(lines.clone()).unwrap();
+        let expected: Canvas = Canvas::new(&lines, |(row, column), c| {
+            let fill: Colour = match (row, column) {
+                (6, 2) | (6, 3) | (6, 7) | (6, 8) => Colour(130, 200, 120),
+                _ if "S-7|FLJ".contains(c) => Colour(240, 140, 50),
+                _ => Colour(205, 215, 235),
+            };
+            CellStyle::new(fill, c)
+        });
+        assert_eq!(pictures, vec![(String::new(), expected)]);
+    }
 }
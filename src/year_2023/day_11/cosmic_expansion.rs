@@ -1,7 +1,9 @@
 use std::collections::HashSet;
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, RngExt};
 
+use crate::{render::{Canvas, CellStyle, Colour}, PuzzleError};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 struct Galaxy {
@@ -41,6 +43,12 @@ impl Image {
         }
     }
 
+    fn draw(&self) -> Vec<String> {
+        (0..self.size.0).map(|row| (0..self.size.1).map(|column| {
+            if self.galaxies.contains(&Galaxy { coord: (row, column) }) { '#' } else { '.' }
+        }).collect()).collect()
+    }
+
     pub fn galaxy_pairs(&self) -> impl Iterator<Item=(&Galaxy, &Galaxy)> + '_ {
         self.galaxies.iter().combinations(2).map(|v| (v[0], v[1]))
     }
@@ -58,20 +66,54 @@ impl From<Vec<String>> for Image {
 }
 
 
-pub fn solve(lines: Vec<String>) {
+/// Generates a `size` by `size` image with a galaxy in roughly one of every twenty pixels and
+/// about a tenth of the rows and columns left empty, so that there is something to expand.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let empty_rows: HashSet<usize> = (0..size).filter(|_| rng.random_bool(0.1)).collect();
+    let empty_columns: HashSet<usize> = (0..size).filter(|_| rng.random_bool(0.1)).collect();
+    (0..size).map(|row| (0..size).map(|column| {
+        let empty: bool = empty_rows.contains(&row) || empty_columns.contains(&column);
+        if !empty && rng.random_bool(0.05) { '#' } else { '.' }
+    }).collect()).collect()
+}
+
+/// Draws the image before and after doubling its empty rows and columns. The rows and columns
+/// that expand are shaded in the first picture, the galaxies are yellow in both.
+pub fn render(lines: Vec<String>) -> Result<Vec<(String, Canvas)>, PuzzleError> {
+    const SPACE: Colour = Colour(20, 20, 50);
+    const EXPANDING: Colour = Colour(60, 60, 110);
+    const GALAXY: Colour = Colour(250, 220, 90);
+    let image: Image = Image::from(lines);
+    let empty_rows: HashSet<usize> = image.expandable_row_indices().collect();
+    let empty_columns: HashSet<usize> = image.expandable_column_indices().collect();
+    let before: Canvas = Canvas::new(&image.draw(), |(row, column), c| {
+        let fill: Colour = match c {
+            '#' => GALAXY,
+            _ if empty_rows.contains(&row) || empty_columns.contains(&column) => EXPANDING,
+            _ => SPACE,
+        };
+        CellStyle::new(fill, c)
+    });
+    let after: Canvas = Canvas::new(&image.expand_image(2).draw(), |_, c| CellStyle::new(if c == '#' { GALAXY } else { SPACE }, c));
+    Ok(vec![("before".to_string(), before), ("after".to_string(), after)])
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
     let image: Image = Image::from(lines);
     let shortest_paths: usize = image.expand_image(2).galaxy_pairs().map(|(x, y)| x.shortest_path_to_other_galaxy(y)).sum();
     println!("Expansion factor of 2: {}", shortest_paths);
     let shortest_paths: usize = image.expand_image(1000000).galaxy_pairs().map(|(x, y)| x.shortest_path_to_other_galaxy(y)).sum();
     println!("Expansion factor of 1,000,000: {}", shortest_paths);
+    Ok(())
 }
 
 
 #[cfg(test)]
 mod test_cosmic_expansion {
     use itertools::Itertools;
+    use rand::{rngs::StdRng, SeedableRng};
 
-    use super::{Galaxy, HashSet, Image};
+    use super::{generate, render, Canvas, CellStyle, Colour, Galaxy, HashSet, Image};
 
     #[test]
     fn shortest_path_between_galaxies() {
@@ -213,4 +255,21 @@ mod test_cosmic_expansion {
         let galaxy_pairs: Vec<(&Galaxy, &Galaxy)> = image.galaxy_pairs().collect();
         assert_eq!(galaxy_pairs.len(), 36);
     }
+
+    #[test]
+    fn generated_image_is_square() {
+        let image: Image = Image::from(generate(&mut StdRng::seed_from_u64(12), 50));
+        assert_eq!(image.size, (50, 50));
+        assert!(!image.galaxies.is_empty());
+        assert!(image.expandable_row_indices().count() > 0);
+    }
+
+    #[test]
+    fn render_before_and_after_expansion() {
+        let pictures = render(vec!["#..".to_string(), "...".to_string(), "..#".to_string()]).unwrap();
+        let names: Vec<&str> = pictures.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["before", "after"]);
+        let expanded: Vec<String> = vec!["#...".to_string(), "....".to_string(), "....".to_string(), "...#".to_string()];
+        assert_eq!(pictures[1].1, Canvas::new(&expanded, |_, c| CellStyle::new(if c == '#' { Colour(250, 220, 90) } else { Colour(20, 20, 50) }, c)));
+    }
 }
@@ -1,176 +1,327 @@
-use std::{str::FromStr, collections::HashMap};
+use std::{str::FromStr, collections::{BTreeMap, BTreeSet}, fmt::Display, fs, path::Path, slice};
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use rand::{rngs::StdRng, seq::IndexedRandom, RngExt};
 use regex::Regex;
 
+use crate::{cli::Args, error::{parse_lines, PuzzleError}};
+
+use super::inference;
 
 #[derive(Debug, PartialEq, Eq)]
-struct Game {
-    id: u32,
-    rounds: Vec<Round>, 
+pub(super) struct Game {
+    pub(super) id: u32,
+    pub(super) rounds: Vec<Round>, 
 }
 
 impl Game {
-    fn least_red_count(&self) -> u32 {
-        self.rounds.iter().max_by(|x, y| x.red.cmp(&y.red)).unwrap().red
-    }
-    fn least_blue_count(&self) -> u32 {
-        self.rounds.iter().max_by(|x, y| x.blue.cmp(&y.blue)).unwrap().blue
+    /// The most cubes of `colour` shown in any round, so the fewest the bag can hold.
+    pub(super) fn least_count(&self, colour: &str) -> u32 {
+        self.rounds.iter().map(|round| round.count(colour)).max().unwrap_or(0)
     }
-    fn least_green_count(&self) -> u32 {
-        self.rounds.iter().max_by(|x, y| x.green.cmp(&y.green)).unwrap().green
+
+    pub(super) fn colours(&self) -> BTreeSet<&str> {
+        self.rounds.iter().flat_map(|round| round.cubes.keys().map(String::as_str)).collect()
     }
-    fn power_set(&self) -> u32 {
-        self.least_red_count() * self.least_green_count() * self.least_blue_count()
+
+    pub(super) fn power_set(&self, colours: &[&str]) -> u32 {
+        colours.iter().map(|colour| self.least_count(colour)).product()
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct ParseGameError;
 impl FromStr for Game {
-    type Err = ParseGameError;
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
             static ref GAME_DATA: Regex = Regex::new(r"^Game (\d+): (.*)$").unwrap();
         }
         if let Some(cap) = GAME_DATA.captures(s) {
-            let id: u32 = cap.get(1).unwrap().as_str().parse::<u32>().unwrap();
-            let rounds: Result<Vec<Round>, _> = cap.get(2).unwrap().as_str().split(";").map(|round_str| round_str.trim().parse::<Round>()).collect();
-            return match rounds {
-                Ok(rounds) => Ok( Game { id, rounds }),
-                Err(_) => Err(ParseGameError),
-            };
+            let id: u32 = cap.get(1).unwrap().as_str().parse::<u32>()?;
+            let rounds: Vec<Round> = cap.get(2).unwrap().as_str().split(";").map(|round_str| round_str.trim().parse::<Round>()).collect::<Result<Vec<Round>, _>>()?;
+            return Ok(Game { id, rounds });
         }
-        Err(ParseGameError)
+        Err(PuzzleError::parse("Game is not of the form 'Game n: rounds'"))
+    }
+}
+
+/// The cubes of each colour shown in a round.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct Round {
+    pub(super) cubes: BTreeMap<String, u32>,
+}
+
+impl Round {
+    fn count(&self, colour: &str) -> u32 {
+        self.cubes.get(colour).copied().unwrap_or(0)
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct Round {
-    red: u32,
-    blue: u32,
-    green: u32,
+/// Counts and colours such as `3 blue, 4 red`, in the order they are written. A colour may only appear once.
+fn parse_cubes(s: &str) -> Result<Vec<(String, u32)>, PuzzleError> {
+    let parse_colour = | colour: &str| {
+        let (count, colour) = colour.split_whitespace().collect_tuple::<(&str, &str)>().unwrap_or(("no number", "no colour"));
+        match (count.parse::<u32>(), colour.to_lowercase()) {
+            (Ok(count), colour) => Ok((colour, count)),
+            _ => Err(PuzzleError::parse(format!("Could not parse '{}' into a cube count and colour", colour.trim()))),
+        }
+    };
+    let cubes: Vec<(String, u32)> = s.split(",").map(parse_colour).collect::<Result<Vec<(String, u32)>, _>>()?;
+    match cubes.iter().map(|(colour, _)| colour).duplicates().next() {
+        Some(colour) => Err(PuzzleError::parse(format!("The colour {colour} is listed more than once in '{}'", s.trim()))),
+        None => Ok(cubes),
+    }
 }
 
 impl FromStr for Round {
-    type Err = ParseGameError;
+    type Err = PuzzleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Round { cubes: parse_cubes(s)?.into_iter().collect() })
+    }
+}
+
+/// The most cubes of each colour the bag holds, written like a round: `12 red, 13 green, 14 blue`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(super) struct Bag {
+    pub(super) limits: Vec<(String, u32)>,
+}
+
+impl Bag {
+    fn classic() -> Self {
+        "12 red, 13 green, 14 blue".parse().expect("the classic bag is valid")
+    }
+
+    fn load(path: &Path) -> Result<Self, PuzzleError> {
+        let contents: String = fs::read_to_string(path).map_err(|e| PuzzleError::execution(format!("Could not read the bag {}: {e}", path.display())))?;
+        contents.trim().parse::<Bag>().map_err(|e| e.with_path(path.display().to_string()))
+    }
+
+    fn colours(&self) -> Vec<&str> {
+        self.limits.iter().map(|(colour, _)| colour.as_str()).collect()
+    }
+
+    pub(super) fn limit(&self, colour: &str) -> Option<u32> {
+        self.limits.iter().find(|(known, _)| known == colour).map(|(_, limit)| *limit)
+    }
+
+    /// Whether every round of `game` could be drawn from the bag. The bag holds none of a colour it
+    /// does not list, unless `strict`, where such a colour is an error.
+    pub(super) fn allows(&self, game: &Game, strict: bool) -> Result<bool, PuzzleError> {
+        if let Some(colour) = game.colours().into_iter().find(|colour| strict && self.limit(colour).is_none()) {
+            return Err(PuzzleError::validation(format!("Game {} shows {colour} cubes, but the bag only holds {}", game.id, self.colours().join(", "))));
+        }
+        Ok(game.colours().into_iter().all(|colour| game.least_count(colour) <= self.limit(colour).unwrap_or(0)))
+    }
+}
+
+impl FromStr for Bag {
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parse_colour = | colour: &str| {
-            let (count, colour) = colour.split_whitespace().collect_tuple::<(&str, &str)>().unwrap_or(("no number", "no colour"));
-            match (count.parse::<u32>(), colour.to_lowercase()) {
-                (Ok(count), colour) => Ok((colour, count)),
-                _ => Err(ParseGameError),
-            }
-        };
-        match s.split(",").map(parse_colour).collect::<Result<Vec<(String, u32)>, _>>() {
-            Ok(colours) => {
-                let colours: HashMap<String, u32> = colours.into_iter().collect();
-                Ok(Round { red: *colours.get(&"red".to_string()).unwrap_or(&0), blue: *colours.get(&"blue".to_string()).unwrap_or(&0), green: *colours.get(&"green".to_string()).unwrap_or(&0) })
-            },
-            _ => Err(ParseGameError),
+        Ok(Bag { limits: parse_cubes(s)? })
+    }
+}
+
+impl Display for Bag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let limits: Vec<String> = self.limits.iter().map(|(colour, limit)| format!("{limit} {colour}")).collect();
+        match limits.split_last() {
+            Some((last, [])) => write!(f, "{last}"),
+            Some((last, rest)) => write!(f, "{} and {last}", rest.join(", ")),
+            None => write!(f, "no"),
+        }
+    }
+}
+
+/// Generates `size` games of one to six rounds, each round revealing up to twenty cubes of some colours.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    (1..=size).map(|id| {
+        let round_count: usize = rng.random_range(1..=6);
+        let rounds: Vec<String> = (0..round_count).map(|_| {
+            let colour_count: usize = rng.random_range(1..=3);
+            ["red", "green", "blue"].sample(rng, colour_count).map(|colour| format!("{} {colour}", rng.random_range(1..=20))).join(", ")
+        }).collect();
+        format!("Game {id}: {}", rounds.join("; "))
+    }).collect()
+}
+
+fn solve_with_bag(lines: &[String], bag: &Bag, strict: bool) -> Result<(), PuzzleError> {
+    // a file ending in a newline leaves an empty last line
+    let end: usize = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1);
+    let games: Vec<Game> = parse_lines(&lines[..end], |s| s.parse::<Game>())?;
+    let mut score: u32 = 0;
+    for (i, game) in games.iter().enumerate() {
+        if bag.allows(game, strict).map_err(|e| e.at_line(i + 1).with_text(&lines[i]))? {
+            score += game.id;
         }
     }
+    println!("ID Sum of Games that could have {bag} balls: {score}");
+    let score: u32 = games.iter().map(|game| game.power_set(&bag.colours())).sum();
+    println!("Total Powerset of the Games: {score}");
+    Ok(())
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    solve_with_bag(&lines, &Bag::classic(), false)
+}
+
+/// Prints the smallest bag of every game and of all games together, then the `top` most likely bags
+/// holding at most `spread` cubes more of each colour than that.
+fn print_inference(lines: &[String], spread: u32, top: usize) -> Result<(), PuzzleError> {
+    let end: usize = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |last| last + 1);
+    let games: Vec<Game> = parse_lines(&lines[..end], |s| s.parse::<Game>())?;
+    for game in games.iter() {
+        let bag: Bag = inference::minimal_bag(slice::from_ref(game));
+        println!("Game {}: at least {bag} cubes, power {}", game.id, inference::power(&bag));
+    }
+    println!("Every game fits a bag of at least {}", inference::minimal_bag(&games));
+    println!("Most likely bags:");
+    for candidate in inference::rank_bags(&games, spread).into_iter().take(top) {
+        println!("  {} (log-likelihood {:.3})", candidate.bag, candidate.log_likelihood);
+    }
+    Ok(())
 }
 
-pub fn solve(lines: Vec<String>) {
-    match lines.into_iter().map(|s| s.parse::<Game>()).collect::<Result<Vec<Game>, ParseGameError>>() {
-        Ok(games) => {
-            let score: u32 = games.iter().filter(|game: &&Game|game.least_red_count() <= 12 && game.least_blue_count() <= 14 && game.least_green_count() <= 13).fold(0, |acc, x| acc + x.id);
-            println!("ID Sum of Games that could have 12 red, 13 green and 14 blue balls: {score}");
-            let score: u32 = games.iter().map(|game| game.power_set()).sum();
-            println!("Total Powerset of the Games: {score}");
-        },
-        Err(e) => eprintln!("Could not parse file. Error: {:?}", e),
+/// Checks the games against the bag given with `--bag`, e.g. `--bag "3 red, 5 yellow"`, or read from
+/// `--bag-file`. With `--strict` a game showing a colour the bag does not list is an error. `--infer`
+/// instead works out which bags the games could have been played with, ranking the bags up to
+/// `--spread` cubes (3 by default) above the smallest one and listing the `--top` (5 by default).
+pub fn solve_with_options(lines: Vec<String>, args: &Args) -> Result<(), PuzzleError> {
+    if args.has("infer") {
+        return print_inference(&lines, args.get("spread")?.unwrap_or(3), args.get("top")?.unwrap_or(5));
     }
+    let bag: Bag = match (args.get::<String>("bag-file")?, args.get::<Bag>("bag")?) {
+        (Some(path), _) => Bag::load(Path::new(&path))?,
+        (None, Some(bag)) => bag,
+        (None, None) => Bag::classic(),
+    };
+    solve_with_bag(&lines, &bag, args.has("strict"))
 }
 
 #[cfg(test)]
 mod cube_conundrum {
+    use rand::SeedableRng;
+
     use crate::year_2023::day_2::cube_conundrum::*;
 
+    fn shown(cubes: &[(&str, u32)]) -> Round {
+        Round { cubes: cubes.iter().map(|(colour, count)| (colour.to_string(), *count)).collect() }
+    }
+
     #[test]
     fn test_parse_game() {
         let game = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green".parse::<Game>().unwrap();
-        assert_eq!(game, Game { id: 1, rounds: vec![Round{red: 4, blue: 3, green: 0}, Round{red: 1, blue: 6, green: 2}, Round{red: 0, blue: 0, green: 2}] });
+        assert_eq!(game, Game { id: 1, rounds: vec![shown(&[("red", 4), ("blue", 3)]), shown(&[("red", 1), ("blue", 6), ("green", 2)]), shown(&[("green", 2)])] });
     }
 
     #[test]
     fn test_parse_game_missing_colour() {
         let game = "Game 1: 3 blue, 4 red; 1 red, 6 blue; 2 red".parse::<Game>().unwrap();
-        assert_eq!(game, Game { id: 1, rounds: vec![Round{red: 4, blue: 3, green: 0}, Round{red: 1, blue: 6, green: 0}, Round{red: 2, blue: 0, green: 0}] });
+        assert_eq!(game, Game { id: 1, rounds: vec![shown(&[("red", 4), ("blue", 3)]), shown(&[("red", 1), ("blue", 6)]), shown(&[("red", 2)])] });
     }
 
     #[test]
     fn test_parse_game_one_round() {
         let game = "Game 1: 3 blue, 4 red".parse::<Game>().unwrap();
-        assert_eq!(game, Game { id: 1, rounds: vec![Round{red: 4, blue: 3, green: 0}] });
+        assert_eq!(game, Game { id: 1, rounds: vec![shown(&[("red", 4), ("blue", 3)])] });
     }
 
     #[test]
     fn test_parse_round() {
         let round = "3 blue, 4 red, 2 green".parse::<Round>().unwrap();
-        assert_eq!(round, Round {red: 4, blue: 3, green: 2});
+        assert_eq!(round, shown(&[("red", 4), ("blue", 3), ("green", 2)]));
     }
 
     #[test]
     fn test_parse_round_one_colour() {
         let round = "3 blue".parse::<Round>().unwrap();
-        assert_eq!(round, Round {red: 0, blue: 3, green: 0});
+        assert_eq!(round, shown(&[("blue", 3)]));
     }
 
     #[test]
     fn test_least_red() {
         let game = "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.least_red_count(), 14);
+        assert_eq!(game.least_count("red"), 14);
     }
 
     #[test]
     fn test_no_red() {
         let game = "Game 4: 1 green, 6 blue; 3 green; 3 green, 15 blue".parse::<Game>().unwrap();
-        assert_eq!(game.least_red_count(), 0);
+        assert_eq!(game.least_count("red"), 0);
     }
 
     #[test]
     fn test_least_green() {
         let game = "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.least_green_count(), 3);
+        assert_eq!(game.least_count("green"), 3);
     }
 
     #[test]
     fn test_no_green() {
         let game = "Game 4: 3 red, 6 blue; 6 red; 15 blue, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.least_green_count(), 0);
+        assert_eq!(game.least_count("green"), 0);
     }
 
     #[test]
     fn test_least_blue() {
         let game = "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.least_blue_count(), 15);
+        assert_eq!(game.least_count("blue"), 15);
     }
 
     #[test]
     fn test_no_blue() {
         let game = "Game 4: 1 green, 3 red; 3 green, 6 red; 3 green, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.least_blue_count(), 0);
+        assert_eq!(game.least_count("blue"), 0);
     }
 
     #[test]
     fn test_powerset() {
         let game = "Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red".parse::<Game>().unwrap();
-        assert_eq!(game.power_set(), 630);
+        assert_eq!(game.power_set(&["red", "green", "blue"]), 630);
     }
 
     #[test]
     fn test_powerset_one_round() {
         let game = "Game 4: 1 green, 3 red, 3 blue".parse::<Game>().unwrap();
-        assert_eq!(game.power_set(), 9);
+        assert_eq!(game.power_set(&["red", "green", "blue"]), 9);
     }
 
     #[test]
     fn test_powerset_one_round_missing_colour() {
         let game = "Game 4: 1 green, 3 red".parse::<Game>().unwrap();
-        assert_eq!(game.power_set(), 0);
+        assert_eq!(game.power_set(&["red", "green", "blue"]), 0);
+    }
+
+    #[test]
+    fn test_generated_games_parse() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(6), 30);
+        let games: Vec<Game> = parse_lines(&lines, |s| s.parse::<Game>()).unwrap();
+        assert_eq!(games.iter().map(|game| game.id).collect::<Vec<u32>>(), (1..=30).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_parse_any_colour() {
+        let round = "2 yellow, 1 Purple".parse::<Round>().unwrap();
+        assert_eq!(round, shown(&[("yellow", 2), ("purple", 1)]));
+        assert!("2 red, 1 red".parse::<Round>().is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_classic_bag() {
+        let games: Vec<Game> = ["Game 1: 3 blue, 4 red; 1 red, 2 green", "Game 3: 8 green, 6 blue, 20 red"].iter().map(|s| s.parse::<Game>().unwrap()).collect();
+        assert_eq!(Bag::classic().to_string(), "12 red, 13 green and 14 blue");
+        assert_eq!(Bag::classic().allows(&games[0], true), Ok(true));
+        assert_eq!(Bag::classic().allows(&games[1], true), Ok(false));
+    }
+
+    #[test]
+    fn test_unknown_colours() {
+        let game = "Game 2: 1 red, 2 yellow".parse::<Game>().unwrap();
+        assert_eq!(Bag::classic().allows(&game, false), Ok(false));
+        assert!(Bag::classic().allows(&game, true).is_err());
+        let bag: Bag = "1 red, 3 yellow".parse().unwrap();
+        assert_eq!(bag.allows(&game, true), Ok(true));
+        assert_eq!(game.power_set(&bag.colours()), 2);
+    }
+}
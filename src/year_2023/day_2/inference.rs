@@ -0,0 +1,118 @@
+use std::collections::BTreeSet;
+
+use itertools::Itertools;
+
+use super::cube_conundrum::{Bag, Game};
+
+/// A bag that could have been used for every game, with the log-likelihood of the games drawn from it.
+#[derive(Debug, PartialEq, Clone)]
+pub(super) struct Candidate {
+    pub(super) bag: Bag,
+    pub(super) log_likelihood: f64,
+}
+
+/// The smallest bag every game could have been played with: for each colour, the most cubes shown
+/// in any single round. For one game this is the bag whose power `power_set` computes.
+pub(super) fn minimal_bag(games: &[Game]) -> Bag {
+    let colours: BTreeSet<&str> = games.iter().flat_map(|game| game.colours()).collect();
+    Bag { limits: colours.into_iter().map(|colour| (colour.to_string(), games.iter().map(|game| game.least_count(colour)).max().unwrap_or(0))).collect() }
+}
+
+/// The product of the cubes of each colour in the bag.
+pub(super) fn power(bag: &Bag) -> u64 {
+    bag.limits.iter().map(|(_, limit)| *limit as u64).product()
+}
+
+/// Every bag consistent with the games holding at most `spread` cubes more of each colour than the minimal bag.
+pub(super) fn consistent_bags(games: &[Game], spread: u32) -> Vec<Bag> {
+    let minimal: Bag = minimal_bag(games);
+    minimal.limits.iter().map(|(colour, least)| (*least..=least + spread).map(move |count| (colour.clone(), count)))
+        .multi_cartesian_product()
+        .map(|limits| Bag { limits })
+        .collect()
+}
+
+/// The natural log of `n` choose `k`.
+fn ln_choose(n: u32, k: u32) -> f64 {
+    (0..k).map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln()).sum()
+}
+
+/// The log of the chance of every round of the games, each round drawing its cubes from the full bag
+/// without putting them back, so a round follows the multivariate hypergeometric distribution.
+/// A bag that cannot have produced the games has a log-likelihood of minus infinity.
+pub(super) fn log_likelihood(bag: &Bag, games: &[Game]) -> f64 {
+    let total: u32 = bag.limits.iter().map(|(_, limit)| limit).sum();
+    games.iter().flat_map(|game| game.rounds.iter()).map(|round| {
+        let drawn: u32 = round.cubes.values().sum();
+        match round.cubes.iter().all(|(colour, count)| *count <= bag.limit(colour).unwrap_or(0)) && drawn <= total {
+            true => round.cubes.iter().map(|(colour, count)| ln_choose(bag.limit(colour).unwrap_or(0), *count)).sum::<f64>() - ln_choose(total, drawn),
+            false => f64::NEG_INFINITY,
+        }
+    }).sum()
+}
+
+/// The bags with the log-likelihood of the games drawn from each, most likely first.
+fn rank(bags: Vec<Bag>, games: &[Game]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = bags.into_iter()
+        .map(|bag| Candidate { log_likelihood: log_likelihood(&bag, games), bag })
+        .collect();
+    candidates.sort_by(|a, b| b.log_likelihood.total_cmp(&a.log_likelihood));
+    candidates
+}
+
+/// The consistent bags within `spread` of the minimal bag, most likely first.
+pub(super) fn rank_bags(games: &[Game], spread: u32) -> Vec<Candidate> {
+    rank(consistent_bags(games, spread), games)
+}
+
+#[cfg(test)]
+mod test_inference {
+    use std::slice;
+
+    use super::*;
+
+    fn games() -> Vec<Game> {
+        [
+            "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green",
+            "Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue",
+            "Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+        ].iter().map(|line| line.parse::<Game>().unwrap()).collect()
+    }
+
+    #[test]
+    fn minimal_bag_per_game_generalises_power_set() {
+        for game in games() {
+            assert_eq!(power(&minimal_bag(slice::from_ref(&game))), game.power_set(&["red", "green", "blue"]) as u64);
+        }
+        assert_eq!(minimal_bag(&games()), "6 blue, 13 green, 20 red".parse::<Bag>().unwrap());
+    }
+
+    #[test]
+    fn consistent_bags_cover_the_spread() {
+        let bags: Vec<Bag> = consistent_bags(&games(), 2);
+        assert_eq!(bags.len(), 27);
+        assert!(bags.iter().all(|bag| games().iter().all(|game| bag.allows(game, true) == Ok(true))));
+    }
+
+    #[test]
+    fn likelihood_matches_the_hypergeometric_distribution() {
+        // drawing 1 red and 1 blue from 2 red and 2 blue: (2 choose 1) * (2 choose 1) / (4 choose 2) = 4 / 6
+        let game: Game = "Game 1: 1 red, 1 blue".parse().unwrap();
+        let bag: Bag = "2 red, 2 blue".parse().unwrap();
+        assert!((log_likelihood(&bag, slice::from_ref(&game)) - (4.0f64 / 6.0).ln()).abs() < 1e-9);
+        assert_eq!(log_likelihood(&"2 red".parse().unwrap(), slice::from_ref(&game)), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn ranking_puts_the_most_likely_bag_first() {
+        let candidates: Vec<Candidate> = rank_bags(&games(), 3);
+        assert_eq!(candidates.len(), 64);
+        assert!(candidates.windows(2).all(|pair| pair[0].log_likelihood >= pair[1].log_likelihood));
+        assert!(candidates.iter().all(|candidate| candidate.log_likelihood.is_finite()));
+        // 2 red are drawn for certain from 2 red, but only a third of the time once a blue is added
+        let game: Game = "Game 1: 2 red".parse().unwrap();
+        let candidates: Vec<Candidate> = rank(vec!["2 red, 1 blue".parse().unwrap(), "2 red, 0 blue".parse().unwrap()], slice::from_ref(&game));
+        assert_eq!(candidates[0].bag, "2 red, 0 blue".parse::<Bag>().unwrap());
+        assert!((candidates[1].log_likelihood - (1.0f64 / 3.0).ln()).abs() < 1e-9);
+    }
+}
@@ -1 +1,2 @@
-pub mod cube_conundrum;
\ No newline at end of file
+pub mod cube_conundrum;
+pub mod inference;
@@ -1,5 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use itertools::iproduct;
+use rand::{rngs::StdRng, RngExt};
+
+use crate::{render::{Canvas, CellStyle, Colour}, PuzzleError};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Position {
@@ -110,14 +113,59 @@ fn is_symbol(symbol: char) -> bool {
     !(symbol.is_ascii_digit() || symbol == '.')
 }
 
-pub fn solve(lines: Vec<String>) {
+/// Generates a `size` by `size` schematic of part numbers up to 999 scattered between symbols,
+/// with every number followed by a `.` so that neighbouring numbers never merge.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    const SYMBOLS: [char; 10] = ['*', '#', '+', '$', '/', '@', '=', '%', '&', '-'];
+    (0..size).map(|_| {
+        let mut row: String = String::new();
+        while row.len() < size {
+            match rng.random_range(0..10) {
+                0 | 1 => row.push_str(&format!("{}.", rng.random_range(1..=999))),
+                2 => row.push(SYMBOLS[rng.random_range(0..SYMBOLS.len())]),
+                _ => row.push('.'),
+            }
+        }
+        row.truncate(size);
+        row
+    }).collect()
+}
+
+/// Highlights part numbers in blue and gears in red, leaving numbers that are not parts grey
+/// and other symbols yellow.
+pub fn render(lines: Vec<String>) -> Result<Vec<(String, Canvas)>, PuzzleError> {
+    const PART: Colour = Colour(120, 170, 240);
+    const NOT_PART: Colour = Colour(200, 200, 200);
+    const GEAR: Colour = Colour(235, 90, 80);
+    const SYMBOL: Colour = Colour(245, 215, 110);
+    let parts: Vec<EnginePart> = parse_engine_parts(&lines);
+    let gears: HashSet<(usize, usize)> = parse_gear_ratio_couples(&lines).into_iter().flat_map(|(part_a, part_b)| {
+        let gears_b: HashSet<(usize, usize)> = part_b.get_gear_positions(&lines).into_iter().collect();
+        part_a.get_gear_positions(&lines).into_iter().filter(move |gear| gears_b.contains(gear))
+    }).collect();
+    let canvas: Canvas = Canvas::new(&lines, |pos, c| {
+        let fill: Colour = match c {
+            _ if gears.contains(&pos) => GEAR,
+            _ if is_symbol(c) => SYMBOL,
+            _ if parts.iter().any(|part| part.position.is_in_position(pos)) => PART,
+            _ if c.is_ascii_digit() => NOT_PART,
+            _ => Colour::WHITE,
+        };
+        CellStyle::new(fill, c)
+    });
+    Ok(vec![(String::new(), canvas)])
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
     println!("Engine Part Sum: {}", parse_engine_parts(&lines).into_iter().map(|part| part.value).sum::<u32>());
     println!("Engine Part Sum: {}", parse_gear_ratio_couples(&lines).into_iter().map(|(part_a, part_b)| part_a.value * part_b.value).sum::<u32>());
-
+    Ok(())
 }
 
 #[cfg(test)]
 mod gear_ratio {
+    use rand::SeedableRng;
+
     use crate::year_2023::day_3::gear_ratio::*;
 
     const EXAMPLE: [&str; 10] = [
@@ -231,4 +279,20 @@ mod gear_ratio {
     fn test_dot_is_not_symbol() {
         assert!(!is_symbol('.'));
     }
+
+    #[test]
+    fn test_render_highlights_parts_and_gears() {
+        let (_, canvas) = render(EXAMPLE.map(String::from).to_vec()).unwrap().remove(0);
+        let fill = |row: usize, column: usize| canvas.cell(row, column).fill;
+        // 467 is a part, 114 is not, the * below them is a gear and the $ is only a symbol
+        assert_eq!([fill(0, 0), fill(0, 5), fill(1, 3), fill(8, 3), fill(0, 3)], [Colour(120, 170, 240), Colour(200, 200, 200), Colour(235, 90, 80), Colour(245, 215, 110), Colour::WHITE]);
+    }
+
+    #[test]
+    fn test_generated_schematic_is_square() {
+        let grid: Vec<String> = generate(&mut StdRng::seed_from_u64(7), 40);
+        assert_eq!(grid.len(), 40);
+        assert!(grid.iter().all(|row| row.len() == 40));
+        assert!(!parse_engine_parts(&grid).is_empty());
+    }
 }
@@ -1,7 +1,8 @@
 use lazy_static::lazy_static;
+use rand::{rngs::StdRng, seq::SliceRandom, RngExt};
 use std::{str, collections};
 
-use crate::ParseInputError;
+use crate::{differential::Variant, error::{parse_lines, PuzzleError}};
 
 #[derive(Debug, PartialEq, Eq)]
 struct Card {
@@ -31,7 +32,7 @@ impl Card {
 }
 
 impl str::FromStr for Card {
-    type Err = ParseInputError;
+    type Err = PuzzleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         lazy_static! {
@@ -42,7 +43,7 @@ impl str::FromStr for Card {
             s.split_whitespace().map(|n| n.parse::<u32>()).collect()
         };
     
-        let caps = GAME_DATA.captures(s).ok_or(regex::Error::Syntax(format!("Card did not match expected pattern: {}", s)))?;
+        let caps = GAME_DATA.captures(s).ok_or(PuzzleError::parse(format!("Card did not match expected pattern: {}", s)))?;
 
         let id: u32 = caps.get(1).unwrap().as_str().parse::<u32>()?;
         let winning_numbers: Vec<u32> = parse_vector_of_numbers(caps.get(2).unwrap().as_str())?;
@@ -76,19 +77,69 @@ fn _copies_won(card: &Card, cards: &Vec<Card>) -> u32 {
     }).sum::<u32>()
 }
 
-pub fn solve(lines: Vec<String>) {
-    let cards: Result<Vec<Card>, ParseInputError> = lines.iter().map(|line| line.parse::<Card>()).collect();
-    if let Ok(cards) = cards {
-        let total_points: u32 = cards.iter().map(|card| card.point()).sum();
-        println!("Total Points: {total_points}");
-        let copies_table = copies_table(&cards);
-        let total_cards_won: u32 = cards.iter().map(|card| 1 + copies_count(card.id, &copies_table)).sum();
-        println!("Total Cards Won: {total_cards_won}");
+/// Generates `size` cards of five winning numbers and eight numbers you have. Most cards win
+/// nothing and no card wins copies of cards past the end of the table.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let format_numbers = |numbers: &[u32]| numbers.iter().map(|n| format!("{n:>2}")).collect::<Vec<String>>().join(" ");
+    (1..=size).map(|id| {
+        let mut numbers: Vec<u32> = (1..100).collect();
+        numbers.shuffle(rng);
+        let matches: usize = if size > id && rng.random_bool(0.3) { rng.random_range(1..=usize::min(5, size - id)) } else { 0 };
+        let (winning_numbers, other_numbers) = numbers.split_at(5);
+        let mut my_numbers: Vec<u32> = winning_numbers[..matches].iter().chain(&other_numbers[..8 - matches]).copied().collect();
+        my_numbers.shuffle(rng);
+        format!("Card {id:>3}: {} | {}", format_numbers(winning_numbers), format_numbers(&my_numbers))
+    }).collect()
+}
+
+/// Cards must be numbered 1, 2, 3, ... in order and may not win copies of cards past the end of the table.
+fn validate_cards(cards: &[Card]) -> Result<(), PuzzleError> {
+    for (i, card) in cards.iter().enumerate() {
+        if card.id as usize != i + 1 {
+            return Err(PuzzleError::validation(format!("Expected card {} but found card {}", i + 1, card.id)).at_line(i + 1));
+        }
+        if card.id + card.count_winning_numbers() > cards.len() as u32 {
+            return Err(PuzzleError::validation(format!("Card {} wins copies of cards past the last card, {}", card.id, cards.len())).at_line(i + 1));
+        }
     }
+    Ok(())
+}
+
+fn parse_cards(lines: &[String]) -> Result<Vec<Card>, PuzzleError> {
+    let cards: Vec<Card> = parse_lines(lines, |line| line.parse::<Card>())?;
+    validate_cards(&cards)?;
+    Ok(cards)
+}
+
+/// The table lookup and the slow recursion both count the total number of cards won.
+pub fn copies_variants() -> [Variant<u32>; 2] {
+    [
+        Variant { name: "copies_count", answer: |lines| {
+            let cards: Vec<Card> = parse_cards(lines)?;
+            let copies_table = copies_table(&cards);
+            Ok(cards.iter().map(|card| 1 + copies_count(card.id, &copies_table)).sum())
+        }},
+        Variant { name: "_copies_won", answer: |lines| {
+            let cards: Vec<Card> = parse_cards(lines)?;
+            Ok(cards.iter().map(|card| 1 + _copies_won(card, &cards)).sum())
+        }},
+    ]
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let cards: Vec<Card> = parse_cards(&lines)?;
+    let total_points: u32 = cards.iter().map(|card| card.point()).sum();
+    println!("Total Points: {total_points}");
+    let copies_table = copies_table(&cards);
+    let total_cards_won: u32 = cards.iter().map(|card| 1 + copies_count(card.id, &copies_table)).sum();
+    println!("Total Cards Won: {total_cards_won}");
+    Ok(())
 }
 
 #[cfg(test)]
 mod scratchcards {
+    use rand::SeedableRng;
+
     use super::*;
 
     const EXAMPLE: [&str; 6] = [
@@ -116,7 +167,7 @@ mod scratchcards {
     #[test]
     fn test_parse_card_no_bar() {
         let line: String = "Card 1: 41 48 83 86 17 83 86  6 31 17  9 48 53".to_string();
-        assert_eq!(line.parse::<Card>().unwrap_err(), ParseInputError {details: format!("Card did not match expected pattern: {line}")});
+        assert_eq!(line.parse::<Card>().unwrap_err(), PuzzleError::parse(format!("Card did not match expected pattern: {line}")));
     }
 
     #[test]
@@ -186,4 +237,29 @@ mod scratchcards {
         let copies_table = copies_table(&cards);
         assert_eq!(copies_count(cards.get(4).unwrap().id, &copies_table), 0);
     }
+
+    #[test]
+    fn test_generated_cards_only_win_existing_cards() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(8), 40);
+        let cards: Vec<Card> = parse_lines(&lines, |line| line.parse::<Card>()).unwrap();
+        assert_eq!(cards.len(), 40);
+        assert!(cards.iter().all(|card| card.cards_ids_won().iter().all(|id| *id <= 40)));
+    }
+
+    #[test]
+    fn test_cards_must_be_numbered_in_order() {
+        let lines: Vec<String> = [EXAMPLE[4].replace("Card 5", "Card 1"), EXAMPLE[5].replace("Card 6", "Card 3")].to_vec();
+        assert_eq!(parse_cards(&lines).unwrap_err().location().line, Some(2));
+    }
+
+    #[test]
+    fn test_cards_cannot_win_past_the_last_card() {
+        let lines: Vec<String> = EXAMPLE[..3].iter().map(|line| line.to_string()).collect();
+        assert!(matches!(parse_cards(&lines), Err(PuzzleError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_copies_variants_agree() {
+        assert_eq!(crate::differential::compare(generate, 20, 0..20, copies_variants()), Ok(20));
+    }
 }
@@ -1,37 +1,37 @@
 use itertools::Itertools;
 
-use crate::ParseInputError;
+use crate::PuzzleError;
 
 type Range = (u64, u64, u64);
 
-fn group_input(lines: Vec<String>) -> Result<(String, Vec<Vec<String>>), ParseInputError> {
+fn group_input(lines: Vec<String>) -> Result<(String, Vec<Vec<String>>), PuzzleError> {
     let groups: Vec<Vec<String>> = lines.split(|line| line == "").map(|line| line.to_vec()).collect::<Vec<_>>();
     let (seeds, mappings) = groups.split_at(1);
-    let seeds = seeds.get(0).unwrap().get(0).unwrap().split(":").nth(1).ok_or(ParseInputError { details: "Seeds line is improperly formatted".to_string() })?;
-    let mappings: Vec<Vec<String>> = mappings.iter().map(|mapping: &Vec<String>| mapping[1..].to_vec()).collect::<Vec<_>>().try_into().map_err(|_| ParseInputError { details: "There aren't 7 mappings".to_string() } )?;
+    let seeds = seeds.first().and_then(|seeds| seeds.first()).and_then(|seeds| seeds.split(":").nth(1)).ok_or(PuzzleError::parse("Seeds line is improperly formatted"))?;
+    let mappings: Vec<Vec<String>> = mappings.iter().map(|mapping: &Vec<String>| mapping.get(1..).map(|ranges| ranges.to_vec()).ok_or(PuzzleError::parse("Mapping is missing its header line"))).collect::<Result<Vec<_>, PuzzleError>>()?;
     Ok((seeds.trim().to_string(), mappings))
 }
 
-fn parse_seeds(seeds: String) -> Result<Vec<u64>, ParseInputError> {
+fn parse_seeds(seeds: String) -> Result<Vec<u64>, PuzzleError> {
     match seeds.split_whitespace().map(|n: &str| n.parse::<u64>()).collect() {
         Ok(ns) => Ok(ns), 
-        Err(_) => Err(ParseInputError { details: "Could not parse seeds to a number".to_string()} ),
+        Err(_) => Err(PuzzleError::parse("Could not parse seeds to a number")),
     }
 }
 
-fn parse_mapping(ranges: Vec<String>) -> Result<Vec<Range>, ParseInputError> {
-    let parse_range = |range: String| -> Result<Range, ParseInputError> {
+fn parse_mapping(ranges: Vec<String>) -> Result<Vec<Range>, PuzzleError> {
+    let parse_range = |range: String| -> Result<Range, PuzzleError> {
         let (dest_start, src_start, range_size): (&str, &str, &str) = range.split_whitespace().collect_tuple().ok_or(
-            ParseInputError{details: "Mapping did not have exactly 3 numbers".to_string()}
+            PuzzleError::parse("Mapping did not have exactly 3 numbers")
         )?;
         Ok((src_start.parse::<u64>()?, dest_start.parse::<u64>()?, range_size.parse::<u64>()?))
     };
     ranges.into_iter().map(parse_range).collect()
 }
 
-pub fn parse(lines: Vec<String>) -> Result<(Vec<u64>, Vec<Vec<Range>>), ParseInputError> {
+pub fn parse(lines: Vec<String>) -> Result<(Vec<u64>, Vec<Vec<Range>>), PuzzleError> {
     let (seeds, groups) = group_input(lines)?;
-    let groups: Vec<Vec<Range>>  = groups.into_iter().map(parse_mapping).collect::<Result<Vec<Vec<Range>>, ParseInputError>>()?;
+    let groups: Vec<Vec<Range>>  = groups.into_iter().map(parse_mapping).collect::<Result<Vec<Vec<Range>>, PuzzleError>>()?;
     Ok((parse_seeds(seeds)?, groups))
 }
 
@@ -56,6 +56,18 @@ mod parse_input {
         ));
     }
     
+    #[test]
+    fn test_missing_seeds_line() {
+        let input: Vec<String> = vec!["".to_string(), "seed-to-soil map:".to_string(), "0 15 37".to_string()];
+        assert!(parse(input).is_err());
+    }
+
+    #[test]
+    fn test_empty_mapping_is_rejected() {
+        let input: Vec<String> = vec!["seeds: 79 14".to_string(), "".to_string(), "".to_string()];
+        assert!(parse(input).is_err());
+    }
+
     #[test]
     fn test_parse_mapping() {
         let input: Vec<String> = vec!["0 15 37".to_string(), "37 52 2".to_string(), "39 0 15".to_string()];
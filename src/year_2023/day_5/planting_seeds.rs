@@ -1,3 +1,8 @@
+use itertools::Itertools;
+use rand::{rngs::StdRng, seq::{IndexedRandom, SliceRandom}, RngExt};
+
+use crate::{differential::Variant, PuzzleError};
+
 use super::parse_input::parse;
 
 type Range = (u64, u64, u64);
@@ -6,34 +11,120 @@ fn is_in_range(x: u64, range: Range) -> bool {
     x >= range.0 && x < range.0 + range.2
 }
 
+fn get_next_value(seed: u64, ranges: &[Range]) -> u64 {
+    match ranges.iter().find(|range: &&(u64, u64, u64)| is_in_range(seed, **range)) {
+        Some(range) => range.1 + (seed - range.0),
+        None => seed,
+    }
+}
+
 fn seed_location(seed: u64, mappings: &Vec<Vec<Range>>) -> u64 {
-    let get_next_value = |seed: u64, ranges: &Vec<Range>| -> u64 {
-        match ranges.iter().find(|range: &&(u64, u64, u64)| is_in_range(seed, **range)) {
-            Some(range) => range.1 + (seed - range.0),
-            None => seed,
-        }
-    };
-    mappings.into_iter().fold(seed, get_next_value)
+    mappings.iter().fold(seed, |value: u64, ranges: &Vec<Range>| get_next_value(value, ranges))
 }
 
 fn reverse_mapping(mappings: Vec<Vec<Range>>) -> Vec<Vec<Range>> {
     mappings.into_iter().map(|v: Vec<(u64, u64, u64)>| v.into_iter().map(|range: (u64, u64, u64)| (range.1, range.0, range.2)).collect()).rev().collect()
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok((seeds, mappings)) = parse(lines) {
-        println!("Minimum Location {}", seeds.iter().map(|seed| seed_location(*seed, &mappings)).min().unwrap());
-        let seeds: Vec<(u64, u64)> = seeds.chunks(2).map(|x| (x[0], x[1])).collect();
-        let mappings: Vec<Vec<Range>> = reverse_mapping(mappings);
-        println!("Minimum Location whe Seeds are Ranges {}", (1..).find(|location: &u64| {
-            let seed: u64 = seed_location(*location, &mappings);
-            seeds.iter().any(|(s, n)| seed >= *s && seed < s + n)
-        }).unwrap());
+/// Every value that `ranges` sends to `value`: one candidate per reversed range covering it, plus
+/// `value` itself passing straight through, keeping only those that really map to `value` going forwards.
+fn preimages(value: u64, ranges: &[Range], reversed: &[Range]) -> Vec<u64> {
+    reversed.iter().filter(|range| is_in_range(value, **range)).map(|range| range.1 + (value - range.0))
+        .chain([value])
+        .filter(|source: &u64| get_next_value(*source, ranges) == value)
+        .unique()
+        .collect()
+}
+
+/// Generates an almanac over the values `0..100 * size`. Each of the seven maps cuts that
+/// interval into `size` ranges and shuffles them. Some ranges are left out, so their values pass
+/// straight through, and some are sent to a random destination that may overlap the others, so
+/// the maps are generally not injective.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    const MAPS: [&str; 7] = ["seed-to-soil", "soil-to-fertilizer", "fertilizer-to-water", "water-to-light", "light-to-temperature", "temperature-to-humidity", "humidity-to-location"];
+    let size: u64 = size.max(1) as u64;
+    let domain: u64 = 100 * size;
+    let seeds: Vec<String> = (0..rng.random_range(1..=5)).map(|_| {
+        let start: u64 = rng.random_range(0..domain - 1);
+        format!("{start} {}", rng.random_range(2..=domain - start))
+    }).collect();
+    let mut lines: Vec<String> = vec![format!("seeds: {}", seeds.join(" "))];
+    for name in MAPS {
+        let mut cuts: Vec<u64> = (1..domain).collect::<Vec<u64>>().sample(rng, size as usize - 1).copied().collect();
+        cuts.extend([0, domain]);
+        cuts.sort();
+        let mut sources: Vec<(u64, u64)> = cuts.windows(2).map(|cut| (cut[0], cut[1] - cut[0])).collect();
+        sources.shuffle(rng);
+        lines.extend([String::new(), format!("{name} map:")]);
+        let mut next_destination: u64 = 0;
+        for (source, length) in sources {
+            if rng.random_bool(0.2) {
+                continue;
+            }
+            let destination: u64 = match rng.random_bool(0.3) {
+                true => rng.random_range(0..=domain - length),
+                false => next_destination,
+            };
+            lines.push(format!("{destination} {source} {length}"));
+            next_destination += length;
+        }
     }
+    lines
+}
+
+fn seed_ranges(seeds: &[u64]) -> Result<Vec<(u64, u64)>, PuzzleError> {
+    match seeds.len() % 2 {
+        0 => Ok(seeds.chunks(2).map(|x| (x[0], x[1])).collect()),
+        _ => Err(PuzzleError::validation("Seed ranges need both a start and a length")),
+    }
+}
+
+/// Walks up from location 0 through the reversed maps until a location leads back to a seed.
+fn lowest_range_location(seeds: &[(u64, u64)], mappings: &[Vec<Range>]) -> Option<u64> {
+    let reversed: Vec<Vec<Range>> = reverse_mapping(mappings.to_vec());
+    // no seed can be further away than the furthest end of any range
+    let bound: u64 = mappings.iter().flatten().map(|range| range.0.max(range.1) + range.2).chain(seeds.iter().map(|(s, n)| s + n)).max()?;
+    (0..bound).find(|location: &u64| {
+        // a map need not be injective, so a location can lead back to several seeds
+        let seeds_found: Vec<u64> = mappings.iter().rev().zip(reversed.iter()).fold(vec![*location], |values: Vec<u64>, (ranges, reversed)| {
+            values.into_iter().flat_map(|value| preimages(value, ranges, reversed)).unique().collect()
+        });
+        seeds_found.iter().any(|seed| seeds.iter().any(|(s, n)| seed >= s && *seed < s + n))
+    })
+}
+
+// slow forward method, maps every single seed
+fn _lowest_range_location(seeds: &[(u64, u64)], mappings: &Vec<Vec<Range>>) -> Option<u64> {
+    seeds.iter().flat_map(|(s, n)| *s..s + n).map(|seed| seed_location(seed, mappings)).min()
+}
+
+/// Mapping every seed forwards and searching the locations backwards both find the lowest location of the seed ranges.
+pub fn range_location_variants() -> [Variant<Option<u64>>; 2] {
+    [
+        Variant { name: "reversed search", answer: |lines| {
+            let (seeds, mappings) = parse(lines.to_vec())?;
+            Ok(lowest_range_location(&seed_ranges(&seeds)?, &mappings))
+        }},
+        Variant { name: "forward mapping", answer: |lines| {
+            let (seeds, mappings) = parse(lines.to_vec())?;
+            Ok(_lowest_range_location(&seed_ranges(&seeds)?, &mappings))
+        }},
+    ]
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let (seeds, mappings) = parse(lines)?;
+    println!("Minimum Location {}", seeds.iter().map(|seed| seed_location(*seed, &mappings)).min().unwrap());
+    let seeds: Vec<(u64, u64)> = seed_ranges(&seeds)?;
+    let location: u64 = lowest_range_location(&seeds, &mappings).ok_or(PuzzleError::validation("No location leads back to a seed"))?;
+    println!("Minimum Location whe Seeds are Ranges {location}");
+    Ok(())
 }
 
 #[cfg(test)]
 pub mod planting_seeds {
+    use rand::SeedableRng;
+
     use crate::year_2023::day_5::planting_seeds::*;
 
     #[test]
@@ -79,4 +170,41 @@ pub mod planting_seeds {
         let pairs: Vec<(u64, u64)> = input.chunks(2).map(|x| (x[0], x[1])).collect();
         assert_eq!(pairs, vec![(79, 14), (55, 13)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generated_maps_stay_in_the_domain() {
+        let (seeds, mappings) = parse(generate(&mut StdRng::seed_from_u64(9), 10)).unwrap();
+        assert_eq!(seeds.len() % 2, 0);
+        assert_eq!(mappings.len(), 7);
+        let locations: std::collections::HashSet<u64> = (0..1000).map(|seed| seed_location(seed, &mappings)).collect();
+        assert!(locations.iter().all(|location| *location < 1000));
+        // overlapping destinations and gaps send several seeds to the same location
+        assert!(locations.len() < 1000);
+    }
+
+    #[test]
+    fn test_odd_seed_count_is_rejected() {
+        assert!(seed_ranges(&[79, 14, 55]).is_err());
+    }
+
+    #[test]
+    fn test_reversed_search_skips_locations_mapped_elsewhere() {
+        // 61 passes straight through the reversed map, but seed 61 is sent to 108 going forwards
+        let mappings: Vec<Vec<Range>> = vec![vec![(24, 71, 46)]];
+        assert_eq!(lowest_range_location(&[(61, 32)], &mappings), Some(70));
+        assert_eq!(_lowest_range_location(&[(61, 32)], &mappings), Some(70));
+    }
+
+    #[test]
+    fn test_reversed_search_follows_every_preimage() {
+        // 55 is reached from seed 5 through the map and from seed 55 passing straight through it
+        let mappings: Vec<Vec<Range>> = vec![vec![(0, 50, 10)]];
+        assert_eq!(lowest_range_location(&[(55, 1)], &mappings), Some(55));
+        assert_eq!(_lowest_range_location(&[(55, 1)], &mappings), Some(55));
+    }
+
+    #[test]
+    fn test_range_location_variants_agree() {
+        assert_eq!(crate::differential::compare(generate, 5, 0..20, range_location_variants()), Ok(20));
+    }
+}
@@ -1,4 +1,6 @@
-use crate::ParseInputError;
+use rand::{rngs::StdRng, RngExt};
+
+use crate::PuzzleError;
 
 #[derive(Debug, PartialEq, Eq)]
 struct Race {
@@ -35,39 +37,58 @@ impl Race {
     }
 }
 
-fn parse_races(lines: (String, String)) -> Result<Vec<Race>, ParseInputError> {
-    let parse_line = |line: String| -> Result<Vec<u64>, ParseInputError> {
-        let digits: &str = line.split(':').nth(1).ok_or(ParseInputError { details: "Nothing delimiting the header from the distances".to_string() })?;
+fn parse_races(lines: (String, String)) -> Result<Vec<Race>, PuzzleError> {
+    let parse_line = |line: String| -> Result<Vec<u64>, PuzzleError> {
+        let digits: &str = line.split(':').nth(1).ok_or(PuzzleError::parse("Nothing delimiting the header from the distances"))?;
         match digits.split_whitespace().map(|n: &str| n.parse::<u64>()).collect() {
             Ok(ns) => Ok(ns), 
-            Err(_) => Err(ParseInputError { details: "Could not parse the digits in a line".to_string()} ),
+            Err(_) => Err(PuzzleError::parse("Could not parse the digits in a line")),
         }
     };
     let (times, distances): (Vec<u64>, Vec<u64>) = (parse_line(lines.0)?, parse_line(lines.1)?);
     Ok(times.into_iter().zip(distances).map(|(time, distance)| Race {time, distance}).collect())
 }
 
-fn parse_race(lines: (String, String)) -> Result<Race, ParseInputError> {
-    let parse_line = |line: String| -> Result<u64, ParseInputError> {
-        let digits: &str = line.split(':').nth(1).ok_or(ParseInputError { details: "Nothing delimiting the header from the distances".to_string() })?;
-        digits.chars().into_iter().filter(|c| c.is_ascii_digit()).collect::<String>().parse::<u64>().map_err(|_| ParseInputError { details: "Failed to parse line into a single digit".to_string() })
+fn parse_race(lines: (String, String)) -> Result<Race, PuzzleError> {
+    let parse_line = |line: String| -> Result<u64, PuzzleError> {
+        let digits: &str = line.split(':').nth(1).ok_or(PuzzleError::parse("Nothing delimiting the header from the distances"))?;
+        digits.chars().into_iter().filter(|c| c.is_ascii_digit()).collect::<String>().parse::<u64>().map_err(|_| PuzzleError::parse("Failed to parse line into a single digit"))
     };
     let (time, distance) = (parse_line(lines.0)?, parse_line(lines.1)?);
     Ok(Race { time, distance})
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok(races) = parse_races((lines.get(0).unwrap().clone(), lines.get(1).unwrap().clone())) {
-        let error_margin: u64 = races.iter().map(|race| race.win_count()).fold(1, |acc: u64, x: u64| acc * x);
-        println!("Error margin: {error_margin}");
-    }
-    if let Ok(race) = parse_race((lines.get(0).unwrap().clone(), lines.get(1).unwrap().clone())) {
-        println!("Win Count: {}", race.win_count());
-    }
+/// Generates between one and `size` races, at most four. Times have two digits and records at
+/// most three, so the single long race of part two stays beatable and fits in a `u64`.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let races: Vec<Race> = (0..size.clamp(1, 4)).map(|_| {
+        let time: u64 = rng.random_range(20..=99);
+        let best_distance: u64 = (time / 2) * (time - time / 2);
+        Race { time, distance: rng.random_range(1..u64::min(1000, best_distance)) }
+    }).collect();
+    vec![
+        format!("Time:    {}", races.iter().map(|race| format!("{:>4}", race.time)).collect::<String>()),
+        format!("Distance:{}", races.iter().map(|race| format!("{:>4}", race.distance)).collect::<String>()),
+    ]
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let (times, distances) = match (lines.first(), lines.get(1)) {
+        (Some(times), Some(distances)) => (times.clone(), distances.clone()),
+        _ => return Err(PuzzleError::parse("Expected a line of times followed by a line of distances")),
+    };
+    let races: Vec<Race> = parse_races((times.clone(), distances.clone()))?;
+    let error_margin: u64 = races.iter().map(|race| race.win_count()).product();
+    println!("Error margin: {error_margin}");
+    let race: Race = parse_race((times, distances))?;
+    println!("Win Count: {}", race.win_count());
+    Ok(())
 }
 
 #[cfg(test)]
 pub mod boat_race {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -101,4 +122,14 @@ pub mod boat_race {
         let race = Race{ time: 30, distance: 200 };
         assert_eq!(race.win_count(), 9);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generated_races_are_winnable() {
+        for seed in 0..50 {
+            let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(seed), 4);
+            let races: Vec<Race> = parse_races((lines[0].clone(), lines[1].clone())).unwrap();
+            assert!(races.iter().all(|race| race.win_count() > 0));
+            assert!(parse_race((lines[0].clone(), lines[1].clone())).unwrap().win_count() > 0);
+        }
+    }
+}
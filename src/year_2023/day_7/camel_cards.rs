@@ -1,14 +1,15 @@
 use std::{cmp, str, collections::{self, HashMap}};
 
 use itertools::Itertools;
+use rand::{rngs::StdRng, seq::IndexedRandom, RngExt};
 
-use crate::ParseInputError;
+use crate::error::{parse_lines, PuzzleError};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 enum Card { Ace, King, Queen, Jack, Tens, Digit (u8), Joker }
 type Cards = [Card; 5];
 impl Card {
-    fn try_parse(value: char, j_card: Card) -> Result<Self, ParseInputError> {
+    fn try_parse(value: char, j_card: Card) -> Result<Self, PuzzleError> {
         match value.to_ascii_uppercase() {
             'A' => Ok(Card::Ace),
             'K' => Ok(Card::King),
@@ -18,7 +19,7 @@ impl Card {
             _ => {
                 match value.to_digit(10) {
                     Some(x) => Ok(Card::Digit(x as u8)),
-                    None => Err(ParseInputError { details: format!("Could not parse {value} into digit") })
+                    None => Err(PuzzleError::parse(format!("Could not parse {value} into a card")))
                 }
             }
         }
@@ -85,10 +86,12 @@ impl Hand {
         Type::determine_type(self.value)
     }
 
-    fn parse(s: &str, j_card: Card) -> Result<Self, ParseInputError> {
-        let (hand, bid) = s.split_whitespace().collect_tuple::<(&str, &str)>().ok_or(ParseInputError {details: "Hand and bid are not delimited by ':'".to_string()})?;
-        let value: Cards = hand.chars().map(|c| Card::try_parse(c, j_card)).collect::<Result<Vec<Card>, _>>()?.try_into().map_err(|_| ParseInputError{ details: "Hand does not consist of exactly 5 cards".to_string() })?;
-        let bid = bid.parse::<u32>()?;
+    fn parse(s: &str, j_card: Card) -> Result<Self, PuzzleError> {
+        let (hand, bid) = s.split_whitespace().collect_tuple::<(&str, &str)>().ok_or(PuzzleError::parse("Hand and bid are not delimited by whitespace"))?;
+        let (hand_column, bid_column) = (s.find(hand).unwrap_or(0) + 1, s.rfind(bid).unwrap_or(0) + 1);
+        let value: Cards = hand.chars().enumerate().map(|(i, c)| Card::try_parse(c, j_card).map_err(|e| e.at_column(hand_column + i)))
+            .collect::<Result<Vec<Card>, _>>()?.try_into().map_err(|_| PuzzleError::parse("Hand does not consist of exactly 5 cards").at_column(hand_column))?;
+        let bid = bid.parse::<u32>().map_err(|e| PuzzleError::from(e).at_column(bid_column))?;
         Ok(Hand { value, bid })
     }
 }
@@ -118,17 +121,27 @@ fn winnings(hands: Vec<Hand>) -> u32{
     sorted_hands.into_iter().enumerate().map(|(i, hand)| (i as u32 + 1) * hand.bid).sum()
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok(hands) = lines.iter().map(|line| Hand::parse(&line, Card::Jack)).collect::<Result<Vec<Hand>, _>>() {
-        println!("Total Winnings: {}", winnings(hands));
-    }
-    if let Ok(hands) = lines.iter().map(|line| Hand::parse(&line, Card::Joker)).collect::<Result<Vec<Hand>, _>>() {
-        println!("Total Winnings: {}", winnings(hands));
-    }
+/// Generates `size` hands of five cards, each with a bid of up to 1000.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    const CARDS: [char; 13] = ['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'];
+    (0..size).map(|_| {
+        let hand: String = (0..5).map(|_| *CARDS.choose(rng).unwrap()).collect();
+        format!("{hand} {}", rng.random_range(1..=1000))
+    }).collect()
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let hands: Vec<Hand> = parse_lines(&lines, |line| Hand::parse(line, Card::Jack))?;
+    println!("Total Winnings: {}", winnings(hands));
+    let hands: Vec<Hand> = parse_lines(&lines, |line| Hand::parse(line, Card::Joker))?;
+    println!("Total Winnings: {}", winnings(hands));
+    Ok(())
 }
 
 #[cfg(test)]
 mod camel_cards {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -148,6 +161,18 @@ mod camel_cards {
         assert!(Card::try_parse('I', Card::Joker).is_err());
     }
 
+    #[test]
+    fn test_parsing_hand_reports_column_of_bad_card() {
+        let err = Hand::parse("  32X4K 765", Card::Jack).unwrap_err();
+        assert_eq!(err.location().column, Some(5));
+    }
+
+    #[test]
+    fn test_parsing_hand_reports_column_of_bad_bid() {
+        let err = Hand::parse("32T3K 7a5", Card::Jack).unwrap_err();
+        assert_eq!(err.location().column, Some(7));
+    }
+
     #[test]
     fn test_card_order() {
         assert!(Card::Ace == Card::Ace);
@@ -232,4 +257,11 @@ mod camel_cards {
         let hand = Hand { value: [Card::Tens, Card::Digit(7), Card::Digit(5), Card::Jack, Card::Digit(5)], bid: 0 };
         assert!(hand == hand);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_generated_hands_parse() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(10), 100);
+        assert_eq!(parse_lines(&lines, |line| Hand::parse(line, Card::Jack)).unwrap().len(), 100);
+        assert_eq!(parse_lines(&lines, |line| Hand::parse(line, Card::Joker)).unwrap().len(), 100);
+    }
+}
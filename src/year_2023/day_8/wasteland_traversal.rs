@@ -1,6 +1,8 @@
 use std::collections;
 
-use crate::ParseInputError;
+use rand::{rngs::StdRng, seq::SliceRandom, RngExt};
+
+use crate::PuzzleError;
 
 #[derive(Debug, PartialEq, Eq)]
 enum Step {
@@ -8,15 +10,15 @@ enum Step {
     Right,
 }
 mod instructions {
-    use crate::ParseInputError;
+    use crate::PuzzleError;
 
     use super::Step;
-    pub fn parse(instructions: String) -> Result<Vec<Step>, ParseInputError> {
-        let parse_char_to_step = |(i, value): (usize, char)| -> Result<Step, ParseInputError> {
+    pub fn parse(instructions: String) -> Result<Vec<Step>, PuzzleError> {
+        let parse_char_to_step = |(i, value): (usize, char)| -> Result<Step, PuzzleError> {
             match value.to_ascii_uppercase() {
                 'L' => Ok(Step::Left),
                 'R' => Ok(Step::Right),
-                _ => Err(ParseInputError { details: format!("Cannot convert the instruction, {value}, in position {i}") }),
+                _ => Err(PuzzleError::parse(format!("Cannot convert the instruction, {value}, in position {i}")).at_column(i + 1)),
             }
         };
         instructions.char_indices().map(parse_char_to_step).collect()
@@ -32,15 +34,16 @@ mod map {
     use lazy_static::lazy_static;
     use regex::Regex;
 
-    use crate::ParseInputError;
+    use crate::error::{mismatch_column, PuzzleError};
     use super::{Node, Map, Step};
 
-    fn parse_node_and_edge(node_and_paths: String) -> Result<(Node, (Node, Node)), ParseInputError> {
+    fn parse_node_and_edge(node_and_paths: String) -> Result<(Node, (Node, Node)), PuzzleError> {
         lazy_static! {
             static ref NODE_RE: Regex = Regex::new(r"(?<name>\w\w\w) = \((?<left>\w\w\w), (?<right>\w\w\w)\)").unwrap();
+            static ref NODE_PIECES: Vec<Regex> = [r"^\w\w\w", r"^ = \(", r"^\w\w\w", r"^, ", r"^\w\w\w", r"^\)"].into_iter().map(|piece| Regex::new(piece).unwrap()).collect();
         }
-        let caps: regex::Captures<'_> = NODE_RE.captures(&node_and_paths).ok_or(
-            ParseInputError { details: format!("{node_and_paths} does not conform to the expected pattern") }
+        let caps: regex::Captures<'_> = NODE_RE.captures(&node_and_paths).ok_or_else(||
+            PuzzleError::parse(format!("{node_and_paths} does not conform to the expected pattern 'AAA = (BBB, CCC)'")).at_column(mismatch_column(&node_and_paths, &NODE_PIECES))
         )?;
         match (caps.name("name"), caps.name("left"), caps.name("right")) {
             (Some(name), Some(left), Some(right)) => { 
@@ -49,13 +52,12 @@ mod map {
                 let right: [char; 3] = right.as_str().chars().collect::<Vec<char>>().try_into().unwrap();
                 Ok((name, (left, right)))
             }
-            _ => Err (ParseInputError { details: "".to_string() }),
+            _ => Err(PuzzleError::parse(format!("{node_and_paths} is missing a node name or path"))),
         }
     }
 
-    pub fn parse(nodes: Vec<String>) -> Result<Map, ParseInputError> {
-        type NodeMap = Vec<(Node, (Node, Node))>;
-        nodes.into_iter().map(parse_node_and_edge).collect::<Result<NodeMap, ParseInputError>>().map(|x: NodeMap| collections::HashMap::from_iter(x))
+    pub fn parse(nodes: Vec<String>) -> Result<Map, PuzzleError> {
+        crate::error::parse_lines(&nodes, |line| parse_node_and_edge(line.to_string())).map(collections::HashMap::from_iter)
     }
 
     pub fn execute(step: &Step, current_node: &Node, map: &Map) -> Node {
@@ -83,31 +85,70 @@ mod map {
     }
 }
 
-fn parse_input(lines: Vec<String>) -> Result<(Vec<Step>, Map), ParseInputError> {
+fn parse_input(lines: Vec<String>) -> Result<(Vec<Step>, Map), PuzzleError> {
     let (instructions, map) = lines.split_at(2);
-    let instructions: Vec<Step>= instructions::parse(instructions[0].to_string())?;
-    let map: collections::HashMap<Node, (Node, Node)> = map::parse(map.to_vec())?;
+    let instructions: Vec<Step>= instructions::parse(instructions[0].to_string()).map_err(|e| e.at_line(1).with_text(instructions[0].as_str()))?;
+    let map: collections::HashMap<Node, (Node, Node)> = map::parse(map.to_vec()).map_err(|e| e.offset_lines(2))?;
     Ok((instructions, map))
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok((instructions, map)) = parse_input(lines) {
-        let step_count: usize = map::step_count(['A', 'A', 'A'], &collections::HashSet::from_iter(vec![['Z', 'Z', 'Z']]), &instructions, &map);
-        println!("Number of steps taken to reach the end {step_count}");
-        
-        // part 2
-        let start_nodes: collections::HashSet<Node> = map.keys().filter_map(|key| if *key.last().unwrap() == 'A' {Some(*key)} else {None}).collect();
-        let destinations: collections::HashSet<Node> = map.keys().filter_map(|key| if *key.last().unwrap() == 'Z' {Some(*key)} else {None}).collect();
-        let step_count: usize = map::step_count_multiple_starts(start_nodes, destinations, &instructions, &map);
-        println!("Step Counts: {:?}", step_count);
-
+/// Generates a map of about `size` nodes (at most 10,000) laid out as up to six disjoint chains,
+/// each running from a node ending in `A` to a node ending in `Z` that loops back into the chain.
+/// Every step moves one or two nodes along the chain, so every start reaches its end whatever the
+/// instructions are. The first chain runs from `AAA` to `ZZZ`.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    let size: usize = size.clamp(2, 10_000);
+    let mut names: collections::HashSet<String> = collections::HashSet::from(["AAA".to_string(), "ZZZ".to_string()]);
+    let mut new_name = |rng: &mut StdRng, last: Option<char>| loop {
+        let last: char = last.unwrap_or_else(|| rng.random_range('B'..='Y'));
+        let name: String = format!("{}{}{last}", rng.random_range('A'..='Z'), rng.random_range('A'..='Z'));
+        if names.insert(name.clone()) { break name; }
+    };
+    let chain_count: usize = rng.random_range(1..=usize::clamp(size / 2, 1, 6));
+    let mut nodes: Vec<String> = Vec::new();
+    for chain_index in 0..chain_count {
+        let length: usize = size / chain_count;
+        let chain: Vec<String> = (0..length).map(|i| match (chain_index, i) {
+            (0, 0) => "AAA".to_string(),
+            (0, i) if i == length - 1 => "ZZZ".to_string(),
+            (_, 0) => new_name(rng, Some('A')),
+            (_, i) if i == length - 1 => new_name(rng, Some('Z')),
+            _ => new_name(rng, None),
+        }).collect();
+        for (i, name) in chain.iter().enumerate() {
+            let (left, right) = if i == length - 1 {
+                (&chain[1], &chain[1])
+            } else {
+                let (next, skip) = (&chain[i + 1], &chain[usize::min(i + 2, length - 1)]);
+                if rng.random_bool(0.5) { (next, skip) } else { (skip, next) }
+            };
+            nodes.push(format!("{name} = ({left}, {right})"));
+        }
     }
+    nodes.shuffle(rng);
+    let instructions: String = (0..rng.random_range(1..=size.min(300))).map(|_| if rng.random_bool(0.5) { 'L' } else { 'R' }).collect();
+    [vec![instructions, String::new()], nodes].concat()
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let (instructions, map) = parse_input(lines)?;
+    let step_count: usize = map::step_count(['A', 'A', 'A'], &collections::HashSet::from_iter(vec![['Z', 'Z', 'Z']]), &instructions, &map);
+    println!("Number of steps taken to reach the end {step_count}");
+
+    // part 2
+    let start_nodes: collections::HashSet<Node> = map.keys().filter_map(|key| if *key.last().unwrap() == 'A' {Some(*key)} else {None}).collect();
+    let destinations: collections::HashSet<Node> = map.keys().filter_map(|key| if *key.last().unwrap() == 'Z' {Some(*key)} else {None}).collect();
+    let step_count: usize = map::step_count_multiple_starts(start_nodes, destinations, &instructions, &map);
+    println!("Step Counts: {:?}", step_count);
+    Ok(())
 }
 
 #[cfg(test)]
 mod wasteland_tests {
     use std::collections::HashMap;
 
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -153,6 +194,23 @@ mod wasteland_tests {
         assert!(map::parse(map_lines).is_err());
     }
 
+    #[test]
+    fn parse_map_reports_line_and_column() {
+        let map_lines = vec!["aaa = (bbb, ccc)".to_string(), "bbb = (ccc)".to_string()];
+        let err = map::parse(map_lines).unwrap_err();
+        assert_eq!(err.location().line, Some(2));
+        assert_eq!(err.location().column, Some(11));
+        assert_eq!(err.location().text, Some("bbb = (ccc)".to_string()));
+    }
+
+    #[test]
+    fn parse_input_offsets_map_lines() {
+        let lines = vec!["LR".to_string(), "".to_string(), "aaa = (bbb, ccc)".to_string(), "bbb = ccc".to_string()];
+        let err = parse_input(lines).unwrap_err();
+        assert_eq!(err.location().line, Some(4));
+        assert_eq!(err.location().column, Some(4));
+    }
+
     #[test]
     fn parse_map_with_less_than_three_char_nodes() {
         let map_lines = vec!["aaa = (bbb, ccc)".to_string(), "b = (ccc, zzz)".to_string()];
@@ -168,4 +226,13 @@ mod wasteland_tests {
         ]);
         assert_eq!(map::parse(map_lines), Ok(maps));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generated_map_reaches_the_end() {
+        for seed in 0..10 {
+            let (instructions, map) = parse_input(generate(&mut StdRng::seed_from_u64(seed), 60)).unwrap();
+            assert!(map.contains_key(&['A', 'A', 'A']));
+            assert!(map::step_count(['A', 'A', 'A'], &collections::HashSet::from([['Z', 'Z', 'Z']]), &instructions, &map) < 60);
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use crate::ParseInputError;
+use rand::{rngs::StdRng, RngExt};
 
-fn parse_history(line: String) -> Result<Vec<i32>, ParseInputError> {
-    line.split_whitespace().map(|x| x.parse::<i32>().map_err(|_| ParseInputError {details: format!("Can't parse {x} to i32")} )).collect()
+use crate::error::{parse_lines, PuzzleError};
+
+fn parse_history(line: String) -> Result<Vec<i32>, PuzzleError> {
+    line.split_whitespace().map(|x| x.parse::<i32>().map_err(|_| PuzzleError::parse(format!("Can't parse {x} to i32")))).collect()
 }
 
 fn predict_next_value(history: &Vec<i32>) -> i32 {
@@ -20,17 +22,28 @@ fn predict_previous_value(history: &Vec<i32>) -> i32 {
     }
 }
 
-pub fn solve(lines: Vec<String>) {
-    if let Ok(history) = lines.into_iter().map(parse_history).collect::<Result<Vec<Vec<i32>>, _>>() {
-        let next_value_sum: i32 = history.iter().map(|row| predict_next_value(row)).sum();
-        println!("Sum of Next Values {next_value_sum}");
-        let prev_value_sum: i32 = history.iter().map(|row| predict_previous_value(row)).sum();
-        println!("Sum of Previous Values {prev_value_sum}");
-    }
+/// Generates `size` histories of 21 readings, each sampled from a random polynomial of degree at
+/// most four with small coefficients, so that the differences always reach zero.
+pub fn generate(rng: &mut StdRng, size: usize) -> Vec<String> {
+    (0..size).map(|_| {
+        let coefficients: Vec<i32> = (0..rng.random_range(1..=5)).map(|_| rng.random_range(-3..=3)).collect();
+        (0..=20).map(|x: i32| coefficients.iter().rev().fold(0, |acc, coefficient| acc * x + coefficient).to_string()).collect::<Vec<String>>().join(" ")
+    }).collect()
+}
+
+pub fn solve(lines: Vec<String>) -> Result<(), PuzzleError> {
+    let history: Vec<Vec<i32>> = parse_lines(&lines, |line| parse_history(line.to_owned()))?;
+    let next_value_sum: i32 = history.iter().map(predict_next_value).sum();
+    println!("Sum of Next Values {next_value_sum}");
+    let prev_value_sum: i32 = history.iter().map(predict_previous_value).sum();
+    println!("Sum of Previous Values {prev_value_sum}");
+    Ok(())
 }
 
 #[cfg(test)]
 mod mirage_maintenance {
+    use rand::SeedableRng;
+
     use super::*;
 
     #[test]
@@ -72,4 +85,14 @@ mod mirage_maintenance {
         let history: &Vec<i32> = &vec![10, 13, 16, 21, 30, 45];
         assert_eq!(predict_previous_value(history), 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn generated_histories_follow_their_polynomial() {
+        let lines: Vec<String> = generate(&mut StdRng::seed_from_u64(11), 20);
+        for history in parse_lines(&lines, |line| parse_history(line.to_owned())).unwrap() {
+            assert_eq!(history.len(), 21);
+            assert_eq!(predict_next_value(&history[..20].to_vec()), history[20]);
+            assert_eq!(predict_previous_value(&history[1..].to_vec()), history[0]);
+        }
+    }
+}